@@ -0,0 +1,125 @@
+// benches/task_listing.rs
+//
+// Timing harness for task listing and stats on a large database:
+// 100k tasks (half of them tagged) and 1M pomodoro session rows.
+//
+// Run with `cargo bench --bench task_listing`. The fixture is built once in
+// the system temp dir and removed when the run finishes.
+
+use std::time::{Duration, Instant};
+
+use code_chrono_lib::database::{Database, StatsGroup, TaskQuery};
+use rusqlite::Connection;
+
+const TASKS:    i64 = 100_000;
+const SESSIONS: i64 = 1_000_000;
+const TAGS:     i64 = 20;
+const RUNS:     u32 = 5;
+
+fn main() {
+    let path = std::env::temp_dir().join("code_chrono_bench.db");
+    let _ = std::fs::remove_file(&path);
+    let path_str = path.to_str().expect("temp path is not valid UTF-8");
+
+    // Let the app create the schema (and indexes), then bulk-load the fixture
+    // through a raw connection inside a single transaction.
+    let db = Database::new(path_str).expect("open database");
+    let started = Instant::now();
+    build_fixture(path_str).expect("build fixture");
+    println!("fixture: {TASKS} tasks, {SESSIONS} sessions in {:?}", started.elapsed());
+
+    bench("get_tasks (all)", || {
+        db.get_tasks(None, None, None, false).unwrap().len()
+    });
+    bench("get_tasks (tag filter)", || {
+        db.get_tasks(None, Some(1), None, false).unwrap().len()
+    });
+    bench("query_tasks (first page of 100)", || {
+        let query = TaskQuery { limit: Some(100), ..TaskQuery::default() };
+        db.query_tasks(&query).unwrap().unwrap().tasks.len()
+    });
+    bench("query_tasks (walk 10 pages)", || {
+        let mut query = TaskQuery { limit: Some(100), ..TaskQuery::default() };
+        let mut seen = 0;
+        for _ in 0..10 {
+            let page = db.query_tasks(&query).unwrap().unwrap();
+            seen += page.tasks.len();
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => break,
+            }
+        }
+        seen
+    });
+    bench("is_external_task_imported", || {
+        db.is_external_task_imported("ext-99999", "GitHub").unwrap() as usize
+    });
+    bench("get_task_stats (30 days)", || {
+        let end = 1_700_000_000 + SESSIONS * 60;
+        db.get_task_stats(end - 30 * 86_400, end).unwrap().len()
+    });
+    bench("get_grouped_stats (project, 30 days)", || {
+        let end = 1_700_000_000 + SESSIONS * 60;
+        db.get_grouped_stats(StatsGroup::Project, end - 30 * 86_400, end).unwrap().len()
+    });
+
+    drop(db);
+    let _ = std::fs::remove_file(&path);
+}
+
+fn build_fixture(path: &str) -> rusqlite::Result<()> {
+    let mut conn = Connection::open(path)?;
+    let tx = conn.transaction()?;
+    {
+        let mut tag = tx.prepare("INSERT INTO tags (name, color) VALUES (?1, NULL)")?;
+        for i in 0..TAGS {
+            tag.execute([format!("tag-{i}")])?;
+        }
+
+        let mut task = tx.prepare(
+            "INSERT INTO tasks (title, status, priority, external_id, source, created_at)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        let mut link = tx.prepare("INSERT INTO task_tags (task_id, tag_id) VALUES (?1, ?2)")?;
+        for i in 0..TASKS {
+            let status = if i % 4 == 0 { "done" } else { "todo" };
+            let (ext, source) = if i % 2 == 0 {
+                (Some(format!("ext-{i}")), Some("GitHub"))
+            } else {
+                (None, None)
+            };
+            task.execute((format!("Task {i}"), status, i % 4, ext, source, 1_700_000_000 + i))?;
+            let id = tx.last_insert_rowid();
+            if i % 2 == 0 {
+                link.execute((id, i % TAGS + 1))?;
+                link.execute((id, (i + 7) % TAGS + 1))?;
+            }
+        }
+
+        let mut session = tx.prepare(
+            "INSERT INTO pomodoro_sessions (task_name, action, elapsed, phase, timestamp, end_timestamp)
+             VALUES (?1, ?2, ?3, 0, ?4, ?4)",
+        )?;
+        for i in 0..SESSIONS {
+            let action = if i % 3 == 0 { "complete" } else { "start" };
+            let ts = 1_700_000_000 + i * 60;
+            session.execute((format!("Task {}", i % TASKS), action, 1500, ts))?;
+        }
+    }
+    tx.commit()
+}
+
+/// Run `f` a few times and print the mean and best wall-clock time.
+fn bench(name: &str, mut f: impl FnMut() -> usize) {
+    let mut total = Duration::ZERO;
+    let mut best  = Duration::MAX;
+    let mut rows  = 0;
+    for _ in 0..RUNS {
+        let started = Instant::now();
+        rows = f();
+        let took = started.elapsed();
+        total += took;
+        best = best.min(took);
+    }
+    println!("{name:<36} rows={rows:<7} mean={:?} best={best:?}", total / RUNS);
+}
@@ -0,0 +1,469 @@
+// billing.rs — Billable hours and invoice drafts
+//
+// Completed work sessions in a date range become one invoice line per
+// project and task. Time is rounded up to the chosen increment, either per
+// session or per day, and priced at the project's hourly rate; a
+// sub-project without its own rate, currency or client inherits them from
+// the nearest parent that has one. Amounts are totalled per currency, since
+// a client's projects aren't guaranteed to share one.
+//
+// This module only does the arithmetic and rendering; the sessions and
+// projects come from the database layer.
+
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+use crate::database::{BillableSession, Calendar, Project};
+
+/// Label for sessions whose task has no project.
+const NO_PROJECT: &str = "No project";
+
+/// What a billing report covers and how time is rounded.
+#[derive(Deserialize, Clone, Debug)]
+pub struct BillingQuery {
+    /// Inclusive time window (unix seconds).
+    pub start: i64,
+    pub end:   i64,
+    /// Limit to one project and its sub-projects.
+    #[serde(default)]
+    pub project_id: Option<i64>,
+    /// Limit to projects billed to this client (case-insensitive).
+    #[serde(default)]
+    pub client: Option<String>,
+    #[serde(default)]
+    pub rounding: Rounding,
+    /// Rounding increment in minutes, e.g. 6, 15 or 30.
+    #[serde(default = "default_increment")]
+    pub round_to_minutes: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Rounding {
+    /// Bill the exact tracked time (to the minute).
+    #[default]
+    None,
+    /// Round every session up on its own.
+    PerSession,
+    /// Round each line's total for a calendar day up.
+    PerDay,
+}
+
+#[derive(Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum InvoiceFormat {
+    Markdown,
+    Html,
+    Csv,
+}
+
+/// One project + task line of a report.
+#[derive(Serialize, Clone, Debug)]
+pub struct InvoiceLine {
+    pub project_id:       Option<i64>,
+    pub project_name:     String,
+    pub task_name:        String,
+    pub sessions:         i64,
+    pub tracked_seconds:  i64,
+    /// Tracked time after rounding.
+    pub billable_minutes: i64,
+    pub hourly_rate:      Option<f64>,
+    pub currency:         Option<String>,
+    /// `None` when the project has no hourly rate.
+    pub amount:           Option<f64>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CurrencyTotal {
+    pub currency:         Option<String>,
+    pub billable_minutes: i64,
+    pub amount:           f64,
+}
+
+/// Returned by `get_billing_report`.
+#[derive(Serialize, Clone, Debug)]
+pub struct BillingReport {
+    pub start:            i64,
+    pub end:              i64,
+    pub client:           Option<String>,
+    pub lines:            Vec<InvoiceLine>,
+    pub tracked_seconds:  i64,
+    pub billable_minutes: i64,
+    /// Priced lines only, one entry per currency.
+    pub totals:           Vec<CurrencyTotal>,
+}
+
+fn default_increment() -> u32 {
+    15
+}
+
+impl BillingQuery {
+    pub fn validate(&self) -> Result<(), String> {
+        if self.end < self.start {
+            return Err("The billing period ends before it starts.".into());
+        }
+        if !(1..=60).contains(&self.round_to_minutes) {
+            return Err("Rounding must be between 1 and 60 minutes.".into());
+        }
+        Ok(())
+    }
+}
+
+/// Group, round and price `sessions` according to `query`. Per-day rounding
+/// uses the days of `calendar`.
+pub fn build_report(
+    query:    &BillingQuery,
+    sessions: &[BillableSession],
+    projects: &[Project],
+    calendar: &Calendar,
+) -> BillingReport {
+    let terms = BillingTerms::resolve(projects);
+    let in_scope = |project_id: Option<i64>| -> bool {
+        if let Some(root) = query.project_id {
+            if !project_id.is_some_and(|id| terms.is_within(id, root)) {
+                return false;
+            }
+        }
+        if let Some(client) = query.client.as_deref().map(str::trim).filter(|c| !c.is_empty()) {
+            let billed_to = project_id.and_then(|id| terms.get(id)).and_then(|t| t.client.as_deref());
+            if !billed_to.is_some_and(|c| c.eq_ignore_ascii_case(client)) {
+                return false;
+            }
+        }
+        true
+    };
+
+    // Running totals per (project, task)
+    let mut groups: BTreeMap<(Option<i64>, String), LineTally> = BTreeMap::new();
+    for session in sessions.iter().filter(|s| in_scope(s.project_id)) {
+        let tally = groups.entry((session.project_id, session.task_name.clone())).or_default();
+        tally.sessions += 1;
+        tally.tracked_seconds += session.elapsed;
+        match query.rounding {
+            Rounding::None => {}
+            Rounding::PerSession => {
+                tally.rounded_minutes += round_up(session.elapsed, query.round_to_minutes);
+            }
+            Rounding::PerDay => {
+                *tally.per_day.entry(calendar.day(session.timestamp)).or_default() += session.elapsed;
+            }
+        }
+    }
+
+    let mut lines: Vec<InvoiceLine> = groups
+        .into_iter()
+        .map(|((project_id, task_name), tally)| {
+            let billable_minutes = match query.rounding {
+                Rounding::None       => (tally.tracked_seconds + 59) / 60,
+                Rounding::PerSession => tally.rounded_minutes,
+                Rounding::PerDay     => tally
+                    .per_day
+                    .values()
+                    .map(|secs| round_up(*secs, query.round_to_minutes))
+                    .sum(),
+            };
+            let project = project_id.and_then(|id| terms.get(id));
+            let hourly_rate = project.and_then(|p| p.hourly_rate);
+            InvoiceLine {
+                project_id,
+                project_name: project.map_or_else(|| NO_PROJECT.to_string(), |p| p.name.clone()),
+                task_name,
+                sessions: tally.sessions,
+                tracked_seconds: tally.tracked_seconds,
+                billable_minutes,
+                hourly_rate,
+                currency: project.and_then(|p| p.currency.clone()),
+                amount: hourly_rate.map(|rate| cents(billable_minutes as f64 / 60.0 * rate)),
+            }
+        })
+        .collect();
+    lines.sort_by(|a, b| a.project_name.cmp(&b.project_name).then_with(|| a.task_name.cmp(&b.task_name)));
+
+    let mut totals: Vec<CurrencyTotal> = Vec::new();
+    for line in &lines {
+        let Some(amount) = line.amount else { continue };
+        match totals.iter_mut().find(|t| t.currency == line.currency) {
+            Some(total) => {
+                total.billable_minutes += line.billable_minutes;
+                total.amount = cents(total.amount + amount);
+            }
+            None => totals.push(CurrencyTotal {
+                currency: line.currency.clone(),
+                billable_minutes: line.billable_minutes,
+                amount,
+            }),
+        }
+    }
+
+    BillingReport {
+        start: query.start,
+        end: query.end,
+        client: query.client.clone().filter(|c| !c.trim().is_empty()),
+        tracked_seconds: lines.iter().map(|l| l.tracked_seconds).sum(),
+        billable_minutes: lines.iter().map(|l| l.billable_minutes).sum(),
+        lines,
+        totals,
+    }
+}
+
+/// Render a report as an invoice draft, with the period's days as
+/// `calendar` counts them.
+pub fn render(report: &BillingReport, format: InvoiceFormat, calendar: &Calendar) -> Result<String, String> {
+    match format {
+        InvoiceFormat::Markdown => Ok(render_markdown(report, calendar)),
+        InvoiceFormat::Html     => Ok(render_html(report, calendar)),
+        InvoiceFormat::Csv      => render_csv(report),
+    }
+}
+
+// ── Rendering ─────────────────────────────────────────────────────────────
+
+fn render_markdown(report: &BillingReport, calendar: &Calendar) -> String {
+    let mut out = format!("# Invoice draft\n\n{}\n\n", heading(report, calendar));
+    out.push_str("| Project | Task | Hours | Rate | Amount |\n");
+    out.push_str("|---|---|---:|---:|---:|\n");
+    for line in &report.lines {
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            md_cell(&line.project_name),
+            md_cell(&line.task_name),
+            hours(line.billable_minutes),
+            line.hourly_rate.map_or_else(String::new, |r| money(r, line.currency.as_deref())),
+            line.amount.map_or_else(String::new, |a| money(a, line.currency.as_deref())),
+        ));
+    }
+    out.push_str(&format!("\n**Total hours:** {}\n", hours(report.billable_minutes)));
+    for total in &report.totals {
+        out.push_str(&format!("\n**Total:** {}\n", money(total.amount, total.currency.as_deref())));
+    }
+    out
+}
+
+fn render_html(report: &BillingReport, calendar: &Calendar) -> String {
+    let mut out = String::from("<!DOCTYPE html>\n<html>\n<head><meta charset=\"utf-8\"><title>Invoice draft</title></head>\n<body>\n");
+    out.push_str(&format!("<h1>Invoice draft</h1>\n<p>{}</p>\n", html_escape(&heading(report, calendar))));
+    out.push_str("<table>\n<thead><tr><th>Project</th><th>Task</th><th>Hours</th><th>Rate</th><th>Amount</th></tr></thead>\n<tbody>\n");
+    for line in &report.lines {
+        out.push_str(&format!(
+            "<tr><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n",
+            html_escape(&line.project_name),
+            html_escape(&line.task_name),
+            hours(line.billable_minutes),
+            html_escape(&line.hourly_rate.map_or_else(String::new, |r| money(r, line.currency.as_deref()))),
+            html_escape(&line.amount.map_or_else(String::new, |a| money(a, line.currency.as_deref()))),
+        ));
+    }
+    out.push_str("</tbody>\n<tfoot>\n");
+    out.push_str(&format!(
+        "<tr><th colspan=\"2\">Total hours</th><td>{}</td><td></td><td></td></tr>\n",
+        hours(report.billable_minutes)
+    ));
+    for total in &report.totals {
+        out.push_str(&format!(
+            "<tr><th colspan=\"4\">Total</th><td>{}</td></tr>\n",
+            html_escape(&money(total.amount, total.currency.as_deref()))
+        ));
+    }
+    out.push_str("</tfoot>\n</table>\n</body>\n</html>\n");
+    out
+}
+
+fn render_csv(report: &BillingReport) -> Result<String, String> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    writer
+        .write_record(["project", "task", "sessions", "tracked_seconds", "billable_hours", "hourly_rate", "currency", "amount"])
+        .map_err(|e| e.to_string())?;
+    for line in &report.lines {
+        writer
+            .write_record([
+                line.project_name.clone(),
+                line.task_name.clone(),
+                line.sessions.to_string(),
+                line.tracked_seconds.to_string(),
+                hours(line.billable_minutes),
+                line.hourly_rate.map_or_else(String::new, |r| format!("{r:.2}")),
+                line.currency.clone().unwrap_or_default(),
+                line.amount.map_or_else(String::new, |a| format!("{a:.2}")),
+            ])
+            .map_err(|e| e.to_string())?;
+    }
+    let bytes = writer.into_inner().map_err(|e| e.to_string())?;
+    String::from_utf8(bytes).map_err(|e| e.to_string())
+}
+
+/// "2024-03-01 – 2024-03-31 · Client: Acme"
+fn heading(report: &BillingReport, calendar: &Calendar) -> String {
+    let day = |ts: i64| calendar.day(ts).format("%Y-%m-%d").to_string();
+    let mut heading = format!("{} – {}", day(report.start), day(report.end));
+    if let Some(client) = &report.client {
+        heading.push_str(&format!(" · Client: {client}"));
+    }
+    heading
+}
+
+fn hours(minutes: i64) -> String {
+    format!("{:.2}", minutes as f64 / 60.0)
+}
+
+fn money(amount: f64, currency: Option<&str>) -> String {
+    match currency {
+        Some(code) => format!("{amount:.2} {code}"),
+        None => format!("{amount:.2}"),
+    }
+}
+
+fn md_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+#[derive(Default)]
+struct LineTally {
+    sessions:        i64,
+    tracked_seconds: i64,
+    rounded_minutes: i64,
+    per_day:         BTreeMap<NaiveDate, i64>,
+}
+
+/// Each project with rate, currency and client filled in from its parents.
+struct BillingTerms {
+    projects: HashMap<i64, Project>,
+}
+
+impl BillingTerms {
+    fn resolve(projects: &[Project]) -> Self {
+        let by_id: HashMap<i64, &Project> = projects.iter().map(|p| (p.id, p)).collect();
+        let projects = projects
+            .iter()
+            .map(|project| {
+                let mut resolved = project.clone();
+                let mut seen = vec![project.id];
+                let mut parent = project.parent_id.and_then(|id| by_id.get(&id));
+                while let Some(p) = parent {
+                    if seen.contains(&p.id) {
+                        break;
+                    }
+                    seen.push(p.id);
+                    if resolved.hourly_rate.is_none() {
+                        resolved.hourly_rate = p.hourly_rate;
+                    }
+                    if resolved.currency.is_none() {
+                        resolved.currency = p.currency.clone();
+                    }
+                    if resolved.client.is_none() {
+                        resolved.client = p.client.clone();
+                    }
+                    parent = p.parent_id.and_then(|id| by_id.get(&id));
+                }
+                (project.id, resolved)
+            })
+            .collect();
+        BillingTerms { projects }
+    }
+
+    fn get(&self, id: i64) -> Option<&Project> {
+        self.projects.get(&id)
+    }
+
+    /// True when `id` is `root` or nested somewhere beneath it.
+    fn is_within(&self, id: i64, root: i64) -> bool {
+        let mut current = Some(id);
+        let mut steps = 0;
+        while let Some(cur) = current {
+            if cur == root {
+                return true;
+            }
+            steps += 1;
+            if steps > self.projects.len() {
+                return false;
+            }
+            current = self.projects.get(&cur).and_then(|p| p.parent_id);
+        }
+        false
+    }
+}
+
+/// Seconds rounded up to a whole number of `increment`-minute blocks,
+/// in minutes.
+fn round_up(seconds: i64, increment: u32) -> i64 {
+    let block = i64::from(increment) * 60;
+    (seconds + block - 1) / block * i64::from(increment)
+}
+
+fn cents(amount: f64) -> f64 {
+    (amount * 100.0).round() / 100.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::Settings;
+
+    #[test]
+    fn round_up_to_whole_increments() {
+        // (seconds, increment in minutes, billed minutes)
+        let cases = [
+            (0,    15, 0),
+            (1,    15, 15),
+            (900,  15, 15),
+            (901,  15, 30),
+            (60,   1,  1),
+            (61,   1,  2),
+            (359,  6,  6),
+            (360,  6,  6),
+            (361,  6,  12),
+            (1799, 30, 30),
+            (3600, 60, 60),
+            (3601, 60, 120),
+        ];
+        for (seconds, increment, minutes) in cases {
+            assert_eq!(round_up(seconds, increment), minutes, "{seconds}s to {increment} min");
+        }
+    }
+
+    #[test]
+    fn cents_round_half_away_from_zero() {
+        let cases = [
+            (67.5,        67.5),
+            (10.0 / 3.0,  3.33),
+            (2.0 / 3.0,   0.67),
+            (0.125,       0.13),
+            (-0.125,      -0.13),
+            // 1.005 is stored just below itself, so it rounds down
+            (1.005,       1.0),
+        ];
+        for (amount, rounded) in cases {
+            assert_eq!(cents(amount), rounded, "{amount}");
+        }
+    }
+
+    #[test]
+    fn heading_uses_calendar_days() {
+        let settings = Settings { timezone: "Asia/Tokyo".into(), day_rollover_hour: 4, ..Settings::default() };
+        let report = BillingReport {
+            // 09:00 on 1 March in Tokyo
+            start:            1_772_323_200,
+            // 03:30 on 1 April in Tokyo, before the rollover: still 31 March
+            end:              1_774_981_800,
+            client:           Some("Acme".into()),
+            lines:            vec![],
+            tracked_seconds:  0,
+            billable_minutes: 0,
+            totals:           vec![],
+        };
+        assert_eq!(
+            heading(&report, &Calendar::from_settings(&settings)),
+            "2026-03-01 – 2026-03-31 · Client: Acme"
+        );
+    }
+}
@@ -0,0 +1,43 @@
+// commands/billing.rs — Billable hours reports and invoice drafts
+//
+// The maths and rendering live in crate::billing; these commands only load
+// the sessions and projects for the requested period.
+
+use std::sync::Arc;
+use tauri::State;
+
+use crate::billing::{self, BillingQuery, BillingReport, InvoiceFormat};
+use crate::commands::with_db;
+use crate::database::Database;
+
+/// Billable time per project and task for a period, rounded and priced.
+#[tauri::command]
+pub async fn get_billing_report(
+    db_state: State<'_, Arc<Database>>,
+    query: BillingQuery,
+) -> Result<BillingReport, String> {
+    report(&db_state, query).await
+}
+
+/// The same report rendered as a Markdown, HTML or CSV invoice draft.
+#[tauri::command]
+pub async fn render_invoice_draft(
+    db_state: State<'_, Arc<Database>>,
+    query:  BillingQuery,
+    format: InvoiceFormat,
+) -> Result<String, String> {
+    let report = report(&db_state, query).await?;
+    billing::render(&report, format, &db_state.calendar())
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+async fn report(db: &Arc<Database>, query: BillingQuery) -> Result<BillingReport, String> {
+    query.validate()?;
+    with_db(db, move |db| {
+        let sessions = db.get_billable_sessions(query.start, query.end)?;
+        let projects = db.get_projects(true)?;
+        Ok::<_, rusqlite::Error>(billing::build_report(&query, &sessions, &projects, &db.calendar()))
+    })
+    .await
+}
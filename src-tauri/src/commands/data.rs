@@ -1,9 +1,10 @@
 // commands/data.rs — CSV import/export and database management
 
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use serde::Deserialize;
 use tauri::State;
 
+use crate::commands::with_db;
 use crate::database::Database;
 
 /// Intermediate struct used during CSV deserialization.
@@ -22,11 +23,10 @@ struct CsvRow {
 /// Export all Pomodoro session records as a CSV string.
 /// The frontend is responsible for triggering the browser "Save file" dialog.
 #[tauri::command]
-pub fn export_csv(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+pub async fn export_csv(
+    db_state: State<'_, Arc<Database>>,
 ) -> Result<String, String> {
-    let db      = db_state.lock().unwrap();
-    let records = db.get_all().map_err(|e| e.to_string())?;
+    let records = with_db(&db_state, |db| db.get_all()).await?;
 
     let mut csv = "id,task_name,action,elapsed,phase,timestamp\n".to_string();
     for r in records {
@@ -44,27 +44,29 @@ pub fn export_csv(
 /// Import Pomodoro session records from a CSV file at `path`.
 /// Returns the number of records inserted.
 #[tauri::command]
-pub fn import_csv(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+pub async fn import_csv(
+    db_state: State<'_, Arc<Database>>,
     path:     String,
 ) -> Result<u64, String> {
-    let db = db_state.lock().unwrap();
-    let mut reader = csv::Reader::from_path(&path).map_err(|e| e.to_string())?;
-    let mut count  = 0u64;
+    with_db(&db_state, move |db| {
+        let mut reader = csv::Reader::from_path(&path).map_err(|e| e.to_string())?;
+        let mut count  = 0u64;
 
-    for result in reader.deserialize() {
-        let row: CsvRow = result.map_err(|e| e.to_string())?;
-        db.insert_record(&row.task_name, &row.action, row.elapsed, row.phase, row.timestamp)
-            .map_err(|e| e.to_string())?;
-        count += 1;
-    }
-    Ok(count)
+        for result in reader.deserialize() {
+            let row: CsvRow = result.map_err(|e| e.to_string())?;
+            db.insert_record(&row.task_name, &row.action, row.elapsed, row.phase, row.timestamp)
+                .map_err(|e| e.to_string())?;
+            count += 1;
+        }
+        Ok::<_, String>(count)
+    })
+    .await
 }
 
 /// Wipe all Pomodoro session logs (tasks, projects, and tags are preserved).
 #[tauri::command]
-pub fn reset_database(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+pub async fn reset_database(
+    db_state: State<'_, Arc<Database>>,
 ) -> Result<(), String> {
-    db_state.lock().unwrap().clear_all().map_err(|e| e.to_string())
+    with_db(&db_state, |db| db.clear_all()).await
 }
@@ -0,0 +1,172 @@
+// commands/encryption.rs
+//
+// Optional encryption at rest (SQLCipher).
+//
+// When the database file is encrypted, startup stops before opening it and
+// the frontend shows an unlock screen. `unlock_database` opens the file with
+// the passphrase and finishes startup (see `start_services` in lib.rs).
+// Until then no `Arc<Database>` state is managed and every data command fails.
+// A database closed by a failed passphrase change is unlocked the same way.
+//
+// The secrets vault is keyed from the same passphrase, so every passphrase
+// change re-encrypts the vault first and rolls it back if the database
+// step fails.
+
+use std::path::Path;
+use std::sync::Arc;
+use rusqlite::ErrorCode;
+use serde::Serialize;
+use tauri::{AppHandle, Manager, State};
+
+use crate::commands::with_db;
+use crate::database::Database;
+use crate::vault::{self, Vault};
+
+/// Location of the database file, managed at startup so the unlock step
+/// can open it later.
+pub struct DatabasePath(pub String);
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EncryptionStatus {
+    /// The database file is encrypted with a passphrase.
+    pub encrypted: bool,
+    /// The database hasn't been unlocked yet in this session, or was closed
+    /// by a failed passphrase change.
+    pub locked:    bool,
+}
+
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+// ── Commands ──────────────────────────────────────────────────────────────
+
+/// Report whether the database is encrypted and whether it still needs unlocking.
+#[tauri::command]
+pub fn get_encryption_status(handle: AppHandle) -> EncryptionStatus {
+    match handle.try_state::<Arc<Database>>() {
+        Some(db) => EncryptionStatus { encrypted: db.is_encrypted(), locked: db.is_closed() },
+        None     => EncryptionStatus { encrypted: true, locked: true },
+    }
+}
+
+/// Open the encrypted database with `passphrase` and start the services
+/// that depend on it, or reopen one closed by a failed passphrase change.
+/// A no-op if the database is already unlocked.
+#[tauri::command]
+pub async fn unlock_database(
+    handle:     AppHandle,
+    path:       State<'_, DatabasePath>,
+    passphrase: String,
+) -> Result<(), String> {
+    if let Some(db) = handle.try_state::<Arc<Database>>() {
+        if !db.is_closed() {
+            return Ok(());
+        }
+        let db = Arc::clone(&db);
+        let vault = Arc::clone(&handle.state::<Arc<Vault>>());
+        return tauri::async_runtime::spawn_blocking(move || {
+            db.reopen(&passphrase).map_err(unlock_error)?;
+            // The secrets may still be under the key of the failed change
+            let key = db.is_encrypted().then_some(passphrase.as_str());
+            vault.rekey(&db, key)
+        })
+        .await
+        .map_err(|e| e.to_string())?;
+    }
+
+    let path = path.0.clone();
+    let (db, vault) = tauri::async_runtime::spawn_blocking(move || {
+        let db = Database::open(&path, Some(&passphrase)).map_err(unlock_error)?;
+        let key_file = Path::new(&path).with_file_name(vault::KEY_FILE);
+        let vault = Vault::open(&db, &key_file, Some(&passphrase))?;
+        Ok::<_, String>((db, vault))
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+
+    crate::start_services(&handle, Arc::new(db), Arc::new(vault));
+    Ok(())
+}
+
+/// Encrypt a plaintext database in place with a new passphrase.
+#[tauri::command]
+pub async fn enable_encryption(
+    db_state:   State<'_, Arc<Database>>,
+    vault:      State<'_, Arc<Vault>>,
+    passphrase: String,
+) -> Result<(), String> {
+    if db_state.is_encrypted() {
+        return Err("The database is already encrypted.".into());
+    }
+    validate_passphrase(&passphrase)?;
+    let vault = Arc::clone(&vault);
+    with_db(&db_state, move |db| rekey(db, &vault, None, Some(&passphrase))).await
+}
+
+/// Re-encrypt the database with a new passphrase.
+#[tauri::command]
+pub async fn change_passphrase(
+    db_state: State<'_, Arc<Database>>,
+    vault:    State<'_, Arc<Vault>>,
+    current:  String,
+    new_passphrase: String,
+) -> Result<(), String> {
+    check_current(&db_state, &current)?;
+    validate_passphrase(&new_passphrase)?;
+    let vault = Arc::clone(&vault);
+    with_db(&db_state, move |db| {
+        rekey(db, &vault, Some(&current), Some(&new_passphrase))
+    })
+    .await
+}
+
+/// Decrypt the database back to plaintext.
+#[tauri::command]
+pub async fn disable_encryption(
+    db_state: State<'_, Arc<Database>>,
+    vault:    State<'_, Arc<Vault>>,
+    current:  String,
+) -> Result<(), String> {
+    check_current(&db_state, &current)?;
+    let vault = Arc::clone(&vault);
+    with_db(&db_state, move |db| rekey(db, &vault, Some(&current), None)).await
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+/// Move the vault and then the database from `old` to `new`. If the
+/// database can't be re-encrypted the vault goes back to `old`, so its
+/// key always matches the passphrase the database is actually under.
+fn rekey(db: &Database, vault: &Vault, old: Option<&str>, new: Option<&str>) -> Result<(), String> {
+    vault.rekey(db, new)?;
+    if let Err(e) = db.set_passphrase(new) {
+        vault
+            .rekey(db, old)
+            .map_err(|undo| format!("{e} (restoring secrets also failed: {undo})"))?;
+        return Err(e.to_string());
+    }
+    Ok(())
+}
+
+fn check_current(db: &Database, current: &str) -> Result<(), String> {
+    if !db.is_encrypted() {
+        return Err("The database is not encrypted.".into());
+    }
+    if !db.check_passphrase(current) {
+        return Err("Incorrect passphrase.".into());
+    }
+    Ok(())
+}
+
+fn unlock_error(e: rusqlite::Error) -> String {
+    match e.sqlite_error_code() {
+        Some(ErrorCode::NotADatabase) => "Incorrect passphrase.".to_string(),
+        _ => e.to_string(),
+    }
+}
+
+fn validate_passphrase(passphrase: &str) -> Result<(), String> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!("Passphrase must be at least {MIN_PASSPHRASE_LEN} characters."));
+    }
+    Ok(())
+}
@@ -4,13 +4,37 @@
 // lib.rs references each via its full path (e.g. commands::timer::start_pomodoro)
 // so Tauri's generate_handler! macro can find the correct function symbols.
 
+pub mod billing;
 pub mod data;
+pub mod encryption;
 pub mod notifications;
 pub mod programs;
 pub mod projects;
+pub mod reminders;
 pub mod settings;
 pub mod stats;
 pub mod sync;
 pub mod tags;
 pub mod tasks;
+pub mod templates;
 pub mod timer;
+
+use std::sync::Arc;
+
+use crate::database::Database;
+
+/// Run a database call on the blocking thread pool and map its error to the
+/// `String` form commands return. Every command (and background loop) goes
+/// through here so SQLite I/O never stalls the async runtime or the timer.
+pub async fn with_db<T, E, F>(db: &Arc<Database>, f: F) -> Result<T, String>
+where
+    T: Send + 'static,
+    E: std::fmt::Display + Send + 'static,
+    F: FnOnce(&Database) -> Result<T, E> + Send + 'static,
+{
+    let db = Arc::clone(db);
+    tauri::async_runtime::spawn_blocking(move || f(&db))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())
+}
@@ -4,11 +4,11 @@
 // settings-aware guards. All notification firing from the frontend
 // should go through these commands so the master-toggle is respected.
 
-use std::sync::{Arc, Mutex};
-use tauri::{AppHandle, State};
+use tauri::{AppHandle, Manager};
 use tauri_plugin_notification::NotificationExt;
 
-use crate::database::Database;
+use crate::commands::settings::{current, SharedSettings};
+use crate::database::Settings;
 
 // ── Commands ──────────────────────────────────────────────────────────────
 
@@ -30,13 +30,12 @@ pub async fn request_notification_permission(handle: AppHandle) -> Result<bool,
 /// Fire a system notification if the master toggle (`notifications_enabled`)
 /// is on. Both title and body are supplied by the caller.
 #[tauri::command]
-pub fn show_notification(
-    handle:   AppHandle,
-    db_state: State<'_, Arc<Mutex<Database>>>,
-    title: String,
-    body:  String,
+pub async fn show_notification(
+    handle: AppHandle,
+    title:  String,
+    body:   String,
 ) -> Result<(), String> {
-    if !notifications_enabled(&db_state) {
+    if !live_settings(&handle).notifications_enabled {
         return Ok(());
     }
     handle
@@ -50,24 +49,11 @@ pub fn show_notification(
 
 // ── Internal helper ───────────────────────────────────────────────────────
 
-/// Reads the `notifications_enabled` setting. Defaults to true if not set.
-pub fn notifications_enabled(db_state: &State<'_, Arc<Mutex<Database>>>) -> bool {
-    db_state
-        .lock()
-        .unwrap()
-        .get_setting("notifications_enabled")
-        .unwrap_or(None)
-        .map(|v| v != "false")
-        .unwrap_or(true)
-}
-
-/// Read any boolean setting, defaulting to `default_val`.
-pub fn bool_setting(db_state: &Arc<Mutex<Database>>, key: &str, default_val: bool) -> bool {
-    db_state
-        .lock()
-        .unwrap()
-        .get_setting(key)
-        .unwrap_or(None)
-        .map(|v| v != "false")
-        .unwrap_or(default_val)
+/// The live settings, or the defaults if startup hasn't finished yet
+/// (e.g. while an encrypted database is still locked).
+pub fn live_settings(handle: &AppHandle) -> Settings {
+    handle
+        .try_state::<SharedSettings>()
+        .map(|shared| current(&shared))
+        .unwrap_or_default()
 }
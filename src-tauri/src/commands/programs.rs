@@ -4,10 +4,12 @@
 // list in SQLite, and the background process-polling loop that emits
 // "program-opened" events to the frontend.
 
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use serde::{Deserialize, Serialize};
 use tauri::{AppHandle, Emitter, State};
 
+use crate::commands::notifications::live_settings;
+use crate::commands::with_db;
 use crate::database::Database;
 
 // ── Data types ────────────────────────────────────────────────────────────
@@ -94,54 +96,38 @@ pub async fn scan_installed_programs() -> Result<Vec<DetectedProgram>, String> {
 
 /// Return all rows from the `tracked_programs` table.
 #[tauri::command]
-pub fn get_tracked_programs(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+pub async fn get_tracked_programs(
+    db_state: State<'_, Arc<Database>>,
 ) -> Result<Vec<TrackedProgram>, String> {
-    db_state
-        .lock()
-        .unwrap()
-        .get_tracked_programs()
-        .map_err(|e| e.to_string())
+    with_db(&db_state, |db| db.get_tracked_programs()).await
 }
 
 /// Upsert a tracked program (insert or update by executable path).
 #[tauri::command]
-pub fn save_tracked_program(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+pub async fn save_tracked_program(
+    db_state: State<'_, Arc<Database>>,
     program:  TrackedProgram,
 ) -> Result<(), String> {
-    db_state
-        .lock()
-        .unwrap()
-        .save_tracked_program(program)
-        .map_err(|e| e.to_string())
+    with_db(&db_state, move |db| db.save_tracked_program(program)).await
 }
 
 /// Delete a tracked program by id.
 #[tauri::command]
-pub fn remove_tracked_program(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+pub async fn remove_tracked_program(
+    db_state: State<'_, Arc<Database>>,
     id:       i64,
 ) -> Result<(), String> {
-    db_state
-        .lock()
-        .unwrap()
-        .remove_tracked_program(id)
-        .map_err(|e| e.to_string())
+    with_db(&db_state, move |db| db.remove_tracked_program(id)).await
 }
 
 /// Toggle the `enabled` flag of a tracked program.
 #[tauri::command]
-pub fn toggle_tracked_program(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+pub async fn toggle_tracked_program(
+    db_state: State<'_, Arc<Database>>,
     id:       i64,
     enabled:  bool,
 ) -> Result<(), String> {
-    db_state
-        .lock()
-        .unwrap()
-        .toggle_tracked_program(id, enabled)
-        .map_err(|e| e.to_string())
+    with_db(&db_state, move |db| db.toggle_tracked_program(id, enabled)).await
 }
 
 // ── Background polling loop ───────────────────────────────────────────────
@@ -151,7 +137,7 @@ pub fn toggle_tracked_program(
 /// emit "program-opened" ONLY when a program transitions from not-running → running.
 /// This prevents the modal from re-appearing if the program was already open when
 /// the 15-minute cooldown window expired without a real launch event.
-pub fn spawn_program_watcher(db: Arc<Mutex<Database>>, handle: AppHandle) {
+pub fn spawn_program_watcher(db: Arc<Database>, handle: AppHandle) {
     tauri::async_runtime::spawn(async move {
         use std::collections::{HashMap, HashSet};
         use std::time::{Duration, Instant};
@@ -170,7 +156,7 @@ pub fn spawn_program_watcher(db: Arc<Mutex<Database>>, handle: AppHandle) {
         // Seed prev_detected from the programs that are already open at startup
         // so we don't immediately fire for an IDE the user already had running.
         sys.refresh_processes(sysinfo::ProcessesToUpdate::All, true);
-        if let Ok(initial) = db.lock().unwrap().get_tracked_programs() {
+        if let Ok(initial) = with_db(&db, |db| db.get_tracked_programs()).await {
             for p in initial.iter().filter(|p| p.enabled) {
                 if process_is_running(&sys, &p.executable) {
                     prev_detected.insert(p.executable.clone());
@@ -181,13 +167,7 @@ pub fn spawn_program_watcher(db: Arc<Mutex<Database>>, handle: AppHandle) {
         loop {
             tokio::time::sleep(Duration::from_secs(5)).await;
 
-            let notify_enabled = db
-                .lock()
-                .unwrap()
-                .get_setting("notify_on_program_open")
-                .unwrap_or(None)
-                .map(|v| v != "false")
-                .unwrap_or(true);
+            let notify_enabled = live_settings(&handle).notify_on_program_open;
 
             // Refresh process list every cycle regardless of the setting so
             // prev_detected stays accurate and won't fire stale events when
@@ -196,7 +176,7 @@ pub fn spawn_program_watcher(db: Arc<Mutex<Database>>, handle: AppHandle) {
 
             if !notify_enabled {
                 // Re-seed so the state is fresh when notifications are re-enabled.
-                if let Ok(tracked) = db.lock().unwrap().get_tracked_programs() {
+                if let Ok(tracked) = with_db(&db, |db| db.get_tracked_programs()).await {
                     prev_detected.clear();
                     for p in tracked.iter().filter(|p| p.enabled) {
                         if process_is_running(&sys, &p.executable) {
@@ -207,7 +187,7 @@ pub fn spawn_program_watcher(db: Arc<Mutex<Database>>, handle: AppHandle) {
                 continue;
             }
 
-            let tracked = match db.lock().unwrap().get_tracked_programs() {
+            let tracked = match with_db(&db, |db| db.get_tracked_programs()).await {
                 Ok(t) => t,
                 Err(_) => continue,
             };
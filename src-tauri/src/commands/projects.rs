@@ -1,32 +1,166 @@
 // commands/projects.rs — Project management
 
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use tauri::State;
 
-use crate::database::{Database, Project};
+use crate::commands::with_db;
+use crate::database::{BudgetStatus, Database, Project, ProjectUpdate, Workflow};
 
+/// Create a project and return its id. Names are unique regardless of case,
+/// so an existing project's id is returned for a name that is already taken.
 #[tauri::command]
 pub async fn create_project(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
     name: String,
     color: Option<String>,
 ) -> Result<i64, String> {
-    db_state.lock().unwrap().create_project(&name, color.as_deref()).map_err(|e| e.to_string())
+    let name = valid_name(name)?;
+    with_db(&db_state, move |db| db.create_project(&name, color.as_deref())).await
 }
 
+/// List projects by name. Archived projects are left out unless
+/// `include_archived` is set.
 #[tauri::command]
 pub async fn get_projects(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
+    include_archived: Option<bool>,
 ) -> Result<Vec<Project>, String> {
-    db_state.lock().unwrap().get_projects().map_err(|e| e.to_string())
+    let include_archived = include_archived.unwrap_or(false);
+    with_db(&db_state, move |db| db.get_projects(include_archived)).await
+}
+
+/// Overwrite a project's name, colour and metadata. The saved project is
+/// returned with its fields normalised (trimmed text, upper-case currency).
+#[tauri::command]
+pub async fn update_project(
+    db_state: State<'_, Arc<Database>>,
+    mut project: Project,
+) -> Result<Project, String> {
+    Database::validate_project(&mut project)?;
+    let saved = project.clone();
+    match with_db(&db_state, move |db| db.update_project(&project).map_err(not_found)).await? {
+        ProjectUpdate::Updated     => Ok(saved),
+        ProjectUpdate::NameTaken   => Err(duplicate(&saved.name)),
+        ProjectUpdate::ParentCycle => {
+            Err("A project can't be nested under itself or one of its sub-projects.".into())
+        }
+    }
+}
+
+#[tauri::command]
+pub async fn rename_project(
+    db_state: State<'_, Arc<Database>>,
+    id: i64,
+    name: String,
+) -> Result<(), String> {
+    let name = valid_name(name)?;
+    let renamed = with_db(&db_state, {
+        let name = name.clone();
+        move |db| db.rename_project(id, &name).map_err(not_found)
+    })
+    .await?;
+    if !renamed {
+        return Err(duplicate(&name));
+    }
+    Ok(())
+}
+
+/// Merge `source_ids` into `target_id`: their tasks and sub-projects move to
+/// the target and the source projects are deleted, atomically. Returns the
+/// number of tasks moved.
+#[tauri::command]
+pub async fn merge_projects(
+    db_state:   State<'_, Arc<Database>>,
+    source_ids: Vec<i64>,
+    target_id:  i64,
+) -> Result<usize, String> {
+    with_db(&db_state, move |db| db.merge_projects(&source_ids, target_id).map_err(not_found))
+        .await?
+        .ok_or_else(|| "A project can't be merged into one of its own sub-projects.".to_string())
+}
+
+/// Archive (or restore, with `archived = false`) a project and its
+/// sub-projects. Their tasks and tracked time are kept.
+#[tauri::command]
+pub async fn set_project_archived(
+    db_state: State<'_, Arc<Database>>,
+    id: i64,
+    archived: bool,
+) -> Result<(), String> {
+    with_db(&db_state, move |db| db.set_project_archived(id, archived).map_err(not_found)).await
+}
+
+/// The workflow tasks in `project_id` follow (the default one for `None` or
+/// a project without its own).
+#[tauri::command]
+pub async fn get_workflow(
+    db_state: State<'_, Arc<Database>>,
+    project_id: Option<i64>,
+) -> Result<Workflow, String> {
+    with_db(&db_state, move |db| db.get_workflow(project_id)).await
+}
+
+/// Give a project its own workflow, or restore the default with `None`.
+/// Fails if the project's tasks use a status the new workflow lacks.
+#[tauri::command]
+pub async fn set_workflow(
+    db_state: State<'_, Arc<Database>>,
+    project_id: i64,
+    mut workflow: Option<Workflow>,
+) -> Result<(), String> {
+    if let Some(workflow) = workflow.as_mut() {
+        Database::validate_workflow(workflow)?;
+    }
+    let orphaned = with_db(&db_state, move |db| {
+        db.set_workflow(project_id, workflow.as_ref()).map_err(not_found)
+    })
+    .await?;
+    if !orphaned.is_empty() {
+        return Err(format!(
+            "Tasks in this project still use statuses the workflow doesn't have: {}.",
+            orphaned.join(", ")
+        ));
+    }
+    Ok(())
+}
+
+/// Budget usage for the dashboard: every non-archived project with a budget,
+/// or just `project_id`.
+#[tauri::command]
+pub async fn get_budget_status(
+    db_state: State<'_, Arc<Database>>,
+    project_id: Option<i64>,
+) -> Result<Vec<BudgetStatus>, String> {
+    with_db(&db_state, move |db| db.get_budget_status(project_id)).await
 }
 
 /// Deletes a project. Tasks assigned to it are automatically un-assigned
 /// by the SQLite ON DELETE SET NULL constraint on tasks.project_id.
 #[tauri::command]
 pub async fn delete_project(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
     id: i64,
 ) -> Result<(), String> {
-    db_state.lock().unwrap().delete_project(id).map_err(|e| e.to_string())
+    with_db(&db_state, move |db| db.delete_project(id)).await
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+fn valid_name(name: String) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Project name must not be empty.".into());
+    }
+    Ok(name.to_string())
+}
+
+fn duplicate(name: &str) -> String {
+    format!("A project named \"{name}\" already exists.")
+}
+
+fn not_found(e: rusqlite::Error) -> String {
+    match e {
+        rusqlite::Error::QueryReturnedNoRows => "Project not found.".to_string(),
+        e => e.to_string(),
+    }
 }
@@ -0,0 +1,93 @@
+// commands/reminders.rs
+//
+// Background loop that watches task due dates and shows a notification at
+// each configured offset before a task is due and once it becomes overdue.
+// What was already shown is logged in the database, so restarts don't repeat it.
+
+use std::sync::Arc;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter};
+use tauri_plugin_notification::NotificationExt;
+
+use crate::commands::notifications::live_settings;
+use crate::commands::with_db;
+use crate::database::{Database, Reminder, ReminderKind};
+
+/// More reminders than this in one pass are shown as a single summary.
+const MAX_INDIVIDUAL: usize = 3;
+
+pub fn spawn_reminder_scheduler(db: Arc<Database>, handle: AppHandle) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let settings = live_settings(&handle);
+
+            // Reminders are taken (and logged) even while notifications are
+            // off, so turning them back on doesn't replay old deadlines.
+            let offsets = settings.due_reminder_offsets.clone();
+            let reminders = with_db(&db, move |db| db.take_due_reminders(&offsets))
+                .await
+                .unwrap_or_default();
+
+            if !reminders.is_empty() {
+                let _ = handle.emit("due-reminders", &reminders);
+
+                if settings.notifications_enabled && settings.notify_due_reminders {
+                    if reminders.len() > MAX_INDIVIDUAL {
+                        notify(&handle, "Task reminders", &summary_body(&reminders));
+                    } else {
+                        for reminder in &reminders {
+                            let title = match reminder.kind {
+                                ReminderKind::Upcoming { .. } => "Due soon",
+                                ReminderKind::Overdue         => "Overdue",
+                            };
+                            notify(&handle, title, &reminder_body(reminder));
+                        }
+                    }
+                }
+            }
+
+            tokio::time::sleep(Duration::from_secs(60)).await;
+        }
+    });
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+fn notify(handle: &AppHandle, title: &str, body: &str) {
+    let _ = handle.notification().builder().title(title).body(body).show();
+}
+
+/// e.g. "Write release notes is due in 1 h." / "Write release notes is overdue."
+fn reminder_body(reminder: &Reminder) -> String {
+    match reminder.kind {
+        ReminderKind::Upcoming { minutes_before } => {
+            format!("{} is due in {}.", reminder.title, format_offset(minutes_before))
+        }
+        ReminderKind::Overdue => format!("{} is overdue.", reminder.title),
+    }
+}
+
+/// e.g. "2 tasks overdue, 3 due soon."
+fn summary_body(reminders: &[Reminder]) -> String {
+    let overdue = reminders.iter().filter(|r| r.kind == ReminderKind::Overdue).count();
+    let upcoming = reminders.len() - overdue;
+    let mut parts = Vec::new();
+    if overdue > 0 {
+        parts.push(format!("{overdue} {} overdue", if overdue == 1 { "task" } else { "tasks" }));
+    }
+    if upcoming > 0 {
+        parts.push(format!("{upcoming} due soon"));
+    }
+    format!("{}.", parts.join(", "))
+}
+
+/// Minutes as the largest whole unit: "2 d", "3 h" or "45 min".
+fn format_offset(minutes: u32) -> String {
+    if minutes.is_multiple_of(1440) {
+        format!("{} d", minutes / 1440)
+    } else if minutes.is_multiple_of(60) {
+        format!("{} h", minutes / 60)
+    } else {
+        format!("{minutes} min")
+    }
+}
@@ -1,25 +1,172 @@
-// commands/settings.rs — Persistent key-value app settings (stored in SQLite)
+// commands/settings.rs — Persistent app settings (stored in SQLite)
+//
+// The frontend reads and writes the typed `Settings` object through
+// `get_settings` / `update_settings`; the older per-key commands remain for
+// compatibility. `get_setting` returns the stored string as it always has,
+// while `set_setting` goes through the same parsing and validation. Every
+// change is broadcast as `settings-changed`, which also keeps the shared
+// copy used by the background loops current.
+//
+// Integration credentials are not settings: they live in the encrypted
+// vault (see crate::vault) and the frontend can only write them or ask
+// whether they're configured. Settings profiles never include them.
 
-use std::sync::{Arc, Mutex};
-use tauri::State;
+use std::sync::{Arc, PoisonError, RwLock};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use tauri::{AppHandle, Emitter, Listener, State};
 
-use crate::database::Database;
+use crate::commands::with_db;
+use crate::database::{Database, Settings};
+use crate::vault::{self, SecretStatus, Vault};
 
-/// Retrieve a setting value by key. Returns `None` if the key doesn't exist yet.
+/// Event emitted with the full `Settings` object after every change.
+pub const SETTINGS_CHANGED: &str = "settings-changed";
+
+/// Live copy of the settings for background loops, so they don't have to
+/// query SQLite on every cycle.
+pub type SharedSettings = Arc<RwLock<Settings>>;
+
+/// Version of the settings profile format written by `export_settings_profile`.
+const PROFILE_VERSION: u32 = 1;
+
+/// A portable snapshot of the settings, without secrets.
+#[derive(Serialize, Deserialize)]
+struct SettingsProfile {
+    version:  u32,
+    settings: Map<String, Value>,
+}
+
+// ── Typed settings ────────────────────────────────────────────────────────
+
+/// Return every setting, with defaults filled in.
+#[tauri::command]
+pub async fn get_settings(db_state: State<'_, Arc<Database>>) -> Result<Settings, String> {
+    with_db(&db_state, |db| db.get_settings()).await
+}
+
+/// Apply a partial update (setting name → value) and return the full,
+/// validated result. Unknown names or invalid values reject the whole update.
 #[tauri::command]
-pub fn get_setting(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+pub async fn update_settings(
+    handle:   AppHandle,
+    db_state: State<'_, Arc<Database>>,
+    changes:  Map<String, Value>,
+) -> Result<Settings, String> {
+    apply(&handle, &db_state, changes, true).await
+}
+
+/// Serialise the current settings as a JSON profile. Secrets are never part
+/// of `Settings`, so the profile is safe to share.
+#[tauri::command]
+pub async fn export_settings_profile(db_state: State<'_, Arc<Database>>) -> Result<String, String> {
+    let settings = with_db(&db_state, |db| db.get_settings()).await?;
+    let profile  = SettingsProfile { version: PROFILE_VERSION, settings: settings.to_map() };
+    serde_json::to_string_pretty(&profile).map_err(|e| e.to_string())
+}
+
+/// Apply a profile produced by `export_settings_profile`. Settings this
+/// version doesn't know are ignored, and credential keys are dropped even if
+/// a hand-edited profile contains them.
+#[tauri::command]
+pub async fn import_settings_profile(
+    handle:   AppHandle,
+    db_state: State<'_, Arc<Database>>,
+    profile:  String,
+) -> Result<Settings, String> {
+    let mut profile: SettingsProfile =
+        serde_json::from_str(&profile).map_err(|e| format!("Invalid settings profile: {e}"))?;
+    if profile.version > PROFILE_VERSION {
+        return Err(format!("Settings profile version {} is not supported.", profile.version));
+    }
+    profile.settings.retain(|key, _| !vault::is_secret_key(key));
+    apply(&handle, &db_state, profile.settings, false).await
+}
+
+// ── Per-key access (legacy) ───────────────────────────────────────────────
+
+/// Retrieve a setting value by key, in its stored string form. Returns
+/// `None` if the key doesn't exist yet. Secret keys always read as `None`.
+#[tauri::command]
+pub async fn get_setting(
+    db_state: State<'_, Arc<Database>>,
     key: String,
 ) -> Result<Option<String>, String> {
-    db_state.lock().unwrap().get_setting(&key).map_err(|e| e.to_string())
+    if vault::is_secret_key(&key) {
+        return Ok(None);
+    }
+    with_db(&db_state, move |db| db.get_setting(&key)).await
 }
 
-/// Upsert a setting value.
+/// Set one setting from its string form ("true", "owner/repo", …).
 #[tauri::command]
 pub async fn set_setting(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    handle:   AppHandle,
+    db_state: State<'_, Arc<Database>>,
     key: String,
     value: String,
 ) -> Result<(), String> {
-    db_state.lock().unwrap().set_setting(&key, &value).map_err(|e| e.to_string())
+    if vault::is_secret_key(&key) {
+        return Err(format!("{key} is a secret; use set_secret instead."));
+    }
+    let value = Settings::parse(&key, &value)?;
+    apply(&handle, &db_state, Map::from_iter([(key, value)]), true).await?;
+    Ok(())
+}
+
+// ── Secrets ───────────────────────────────────────────────────────────────
+
+/// Encrypt and store a credential. An empty value removes it.
+#[tauri::command]
+pub async fn set_secret(
+    db_state: State<'_, Arc<Database>>,
+    vault:    State<'_, Arc<Vault>>,
+    name:     String,
+    value:    String,
+) -> Result<(), String> {
+    let vault = Arc::clone(&vault);
+    with_db(&db_state, move |db| vault.set(db, &name, value.trim())).await
+}
+
+/// Report which credentials are configured, without revealing them.
+#[tauri::command]
+pub async fn get_secret_status(
+    db_state: State<'_, Arc<Database>>,
+) -> Result<Vec<SecretStatus>, String> {
+    with_db(&db_state, Vault::status).await
+}
+
+// ── Shared state ──────────────────────────────────────────────────────────
+
+/// Load the settings for the background loops and keep that copy current
+/// by listening for `settings-changed`. Falls back to the defaults if the
+/// initial read fails.
+pub fn watch_settings(handle: &AppHandle, db: &Database) -> SharedSettings {
+    let shared = Arc::new(RwLock::new(db.get_settings().unwrap_or_default()));
+    let target = Arc::clone(&shared);
+    handle.listen(SETTINGS_CHANGED, move |event| {
+        if let Ok(settings) = serde_json::from_str::<Settings>(event.payload()) {
+            *target.write().unwrap_or_else(PoisonError::into_inner) = settings;
+        }
+    });
+    shared
+}
+
+/// Read the current settings from the shared copy.
+pub fn current(shared: &SharedSettings) -> Settings {
+    shared.read().unwrap_or_else(PoisonError::into_inner).clone()
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+/// Merge `changes` into the stored settings, validate, save and broadcast.
+async fn apply(
+    handle:  &AppHandle,
+    db:      &Arc<Database>,
+    changes: Map<String, Value>,
+    strict:  bool,
+) -> Result<Settings, String> {
+    let settings = with_db(db, move |db| db.update_settings(changes, strict)).await??;
+    let _ = handle.emit(SETTINGS_CHANGED, settings.clone());
+    Ok(settings)
 }
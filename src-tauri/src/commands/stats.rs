@@ -1,36 +1,115 @@
 // commands/stats.rs — Session statistics queries
 
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use tauri::State;
 
-use crate::database::{Database, DailyStats, TaskStats};
+use crate::commands::with_db;
+use crate::database::{
+    Database, DailyStats, FocusHeatmap, FocusReport, GroupStats, PeriodTrend, SeriesBy, StatsGroup,
+    SwitchReport, TaskStats, TrendPeriod, TrendSeries,
+};
+
+/// Lines drawn by `get_trend_series` when the caller doesn't say.
+const DEFAULT_SERIES_LIMIT: usize = 8;
 
 /// Returns total time tracked and session count, grouped by task name.
 /// Useful for the "Time by Task" bar chart.
 #[tauri::command]
-pub fn get_task_stats(
-    db_state:        State<'_, Arc<Mutex<Database>>>,
+pub async fn get_task_stats(
+    db_state:        State<'_, Arc<Database>>,
     start_timestamp: i64,
     end_timestamp:   i64,
 ) -> Result<Vec<TaskStats>, String> {
-    db_state
-        .lock()
-        .unwrap()
-        .get_task_stats(start_timestamp, end_timestamp)
-        .map_err(|e| e.to_string())
+    with_db(&db_state, move |db| db.get_task_stats(start_timestamp, end_timestamp)).await
 }
 
 /// Returns the same aggregated stats broken down day by day.
 /// Useful for the "Daily Breakdown" panel.
 #[tauri::command]
-pub fn get_daily_breakdown(
-    db_state:        State<'_, Arc<Mutex<Database>>>,
+pub async fn get_daily_breakdown(
+    db_state:        State<'_, Arc<Database>>,
     start_timestamp: i64,
     end_timestamp:   i64,
 ) -> Result<Vec<DailyStats>, String> {
-    db_state
-        .lock()
-        .unwrap()
-        .get_daily_breakdown(start_timestamp, end_timestamp)
-        .map_err(|e| e.to_string())
+    with_db(&db_state, move |db| db.get_daily_breakdown(start_timestamp, end_timestamp)).await
+}
+
+/// Returns work time grouped by project, tag, integration source or status.
+/// Sessions are linked to tasks by title; those matching no task (or whose
+/// task has no project, tag or source) land in the unassigned bucket,
+/// which has no `name`.
+#[tauri::command]
+pub async fn get_grouped_stats(
+    db_state:        State<'_, Arc<Database>>,
+    group:           StatsGroup,
+    start_timestamp: i64,
+    end_timestamp:   i64,
+) -> Result<Vec<GroupStats>, String> {
+    with_db(&db_state, move |db| db.get_grouped_stats(group, start_timestamp, end_timestamp)).await
+}
+
+/// Returns work totals per week or month for every period touching the
+/// range, with the average per working day and the change against the
+/// previous period and the same period last year.
+#[tauri::command]
+pub async fn get_trends(
+    db_state:        State<'_, Arc<Database>>,
+    period:          TrendPeriod,
+    start_timestamp: i64,
+    end_timestamp:   i64,
+) -> Result<Vec<PeriodTrend>, String> {
+    with_db(&db_state, move |db| db.get_trends(period, start_timestamp, end_timestamp)).await
+}
+
+/// Returns one line of work time per period for each of the busiest tasks
+/// or projects (at most `limit`, default 8). Useful for trend charts.
+#[tauri::command]
+pub async fn get_trend_series(
+    db_state:        State<'_, Arc<Database>>,
+    period:          TrendPeriod,
+    by:              SeriesBy,
+    start_timestamp: i64,
+    end_timestamp:   i64,
+    limit:           Option<usize>,
+) -> Result<TrendSeries, String> {
+    let limit = limit.unwrap_or(DEFAULT_SERIES_LIMIT);
+    with_db(&db_state, move |db| {
+        db.get_trend_series(period, by, start_timestamp, end_timestamp, limit)
+    })
+    .await
+}
+
+/// Returns a weekday × hour matrix of focus minutes and session counts in
+/// the configured timezone. Sessions spanning several hours are split.
+#[tauri::command]
+pub async fn get_focus_heatmap(
+    db_state:        State<'_, Arc<Database>>,
+    start_timestamp: i64,
+    end_timestamp:   i64,
+) -> Result<FocusHeatmap, String> {
+    with_db(&db_state, move |db| db.get_focus_heatmap(start_timestamp, end_timestamp)).await
+}
+
+/// Returns focus metrics for each work session started in the range and
+/// per day: pauses, pause time, completed vs reset sessions, average
+/// uninterrupted stretch and a 0–100 focus score.
+#[tauri::command]
+pub async fn get_focus_metrics(
+    db_state:        State<'_, Arc<Database>>,
+    start_timestamp: i64,
+    end_timestamp:   i64,
+) -> Result<FocusReport, String> {
+    with_db(&db_state, move |db| db.get_focus_metrics(start_timestamp, end_timestamp)).await
+}
+
+/// Returns task switches per day, the average work time on a task before
+/// switching away, and the most frequent from → to pairs. Switches within a
+/// running session are counted separately as `mid_session`.
+#[tauri::command]
+pub async fn get_switch_stats(
+    db_state:        State<'_, Arc<Database>>,
+    start_timestamp: i64,
+    end_timestamp:   i64,
+) -> Result<SwitchReport, String> {
+    with_db(&db_state, move |db| db.get_switch_stats(start_timestamp, end_timestamp)).await
 }
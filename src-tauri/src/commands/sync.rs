@@ -8,16 +8,41 @@
 //                        delegates to preview + import all non-imported.
 //
 // Deduplication is always enforced by `external_id + source` in the DB.
+// API tokens are read from the encrypted vault, never from settings.
 
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use tauri::State;
 
+use crate::commands::with_db;
 use crate::database::{Database, Task};
 use crate::integrations::ExternalTask;
+use crate::vault::Vault;
 
 // ── Private helpers ───────────────────────────────────────────────────────
 
+/// Decrypt an integration's token from the vault and read its plain
+/// `settings` (host, domain, …) alongside it. Blank values count as not
+/// configured, so the caller's "not configured" message is what users see.
+fn credentials<const N: usize>(
+    db:       &Database,
+    vault:    &Vault,
+    secret:   &str,
+    settings: [&str; N],
+) -> Result<(Option<String>, [Option<String>; N]), String> {
+    let token = vault.get(db, secret)?.filter(|t| !t.trim().is_empty());
+    let mut values: [Option<String>; N] = std::array::from_fn(|_| None);
+    for (value, key) in values.iter_mut().zip(settings) {
+        *value = db
+            .get_setting(key)
+            .map_err(|e| e.to_string())?
+            .filter(|v| !v.trim().is_empty());
+    }
+    Ok((token, values))
+}
+
 /// Convert an ExternalTask + source label into a database Task ready to insert.
+/// `status` is still the platform's; `import_status` maps it once the
+/// project is known.
 fn to_db_task(et: ExternalTask, source: &str) -> Task {
     Task {
         id:           0,
@@ -25,7 +50,7 @@ fn to_db_task(et: ExternalTask, source: &str) -> Task {
         description:  et.description,
         due_date:     None,
         priority:     1,
-        status:       "todo".to_string(),
+        status:       et.status,
         project_id:   None,
         parent_id:    None,
         position:     0,
@@ -34,6 +59,9 @@ fn to_db_task(et: ExternalTask, source: &str) -> Task {
         created_at:   0,
         completed_at: None,
         tags:         vec![],
+        estimate_minutes: None,
+        recurrence:   None,
+        blocked:      false,
     }
 }
 
@@ -66,6 +94,7 @@ fn save_by_ids(db: &Database, tasks: Vec<ExternalTask>, ids: &[String], source:
                     }
                 }
             }
+            db_task.status = db.import_status(db_task.project_id, &db_task.status).ok()?;
             db.save_external_task(db_task).ok()
         })
         .count()
@@ -75,7 +104,11 @@ fn save_by_ids(db: &Database, tasks: Vec<ExternalTask>, ids: &[String], source:
 fn save_all(db: &Database, tasks: Vec<ExternalTask>, source: &str) -> usize {
     tasks
         .into_iter()
-        .filter_map(|et| db.save_external_task(to_db_task(et, source)).ok())
+        .filter_map(|et| {
+            let mut db_task = to_db_task(et, source);
+            db_task.status = db.import_status(None, &db_task.status).ok()?;
+            db.save_external_task(db_task).ok()
+        })
         .count()
 }
 
@@ -85,62 +118,55 @@ fn save_all(db: &Database, tasks: Vec<ExternalTask>, source: &str) -> usize {
 /// `already_imported: true` for any issue already present in the local DB.
 #[tauri::command]
 pub async fn preview_sync_github(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
+    vault:    State<'_, Arc<Vault>>,
 ) -> Result<Vec<ExternalTask>, String> {
-    let (token, repo) = {
-        let db = db_state.lock().unwrap();
-        (
-            db.get_setting("github_token").unwrap_or(None),
-            db.get_setting("github_repo").unwrap_or(None),
-        )
-    };
+    let vault = Arc::clone(&vault);
+    let (token, [repo]) = with_db(&db_state, move |db| {
+        credentials(db, &vault, "github_token", ["github_repo"])
+    })
+    .await?;
     let token = token.ok_or("GitHub token not configured. Go to Settings → Integrations.")?;
 
     let tasks = crate::integrations::fetch_github_tasks(&token, repo.as_deref()).await?;
-    let db = db_state.lock().unwrap();
-    Ok(mark_imported(&db, tasks, "GitHub"))
+    with_db(&db_state, move |db| Ok::<_, String>(mark_imported(db, tasks, "GitHub"))).await
 }
 
 /// Fetch Jira issues without importing them.
 #[tauri::command]
 pub async fn preview_sync_jira(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
+    vault:    State<'_, Arc<Vault>>,
 ) -> Result<Vec<ExternalTask>, String> {
-    let (domain, email, token) = {
-        let db = db_state.lock().unwrap();
-        (
-            db.get_setting("jira_domain").unwrap_or(None),
-            db.get_setting("jira_email").unwrap_or(None),
-            db.get_setting("jira_token").unwrap_or(None),
-        )
-    };
+    let vault = Arc::clone(&vault);
+    let (token, [domain, email]) = with_db(&db_state, move |db| {
+        credentials(db, &vault, "jira_token", ["jira_domain", "jira_email"])
+    })
+    .await?;
     let domain = domain.ok_or("Jira domain not configured. Go to Settings → Integrations.")?;
     let email  = email.ok_or("Jira email not configured. Go to Settings → Integrations.")?;
     let token  = token.ok_or("Jira API token not configured. Go to Settings → Integrations.")?;
 
     let tasks = crate::integrations::fetch_jira_tasks(&domain, &email, &token).await?;
-    let db = db_state.lock().unwrap();
-    Ok(mark_imported(&db, tasks, "Jira"))
+    with_db(&db_state, move |db| Ok::<_, String>(mark_imported(db, tasks, "Jira"))).await
 }
 
 /// Fetch GitLab issues without importing them.
 #[tauri::command]
 pub async fn preview_sync_gitlab(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
+    vault:    State<'_, Arc<Vault>>,
 ) -> Result<Vec<ExternalTask>, String> {
-    let (token, host) = {
-        let db = db_state.lock().unwrap();
-        (
-            db.get_setting("gitlab_token").unwrap_or(None),
-            db.get_setting("gitlab_host").unwrap_or(None),
-        )
-    };
+    let vault = Arc::clone(&vault);
+    let (token, [host]) = with_db(&db_state, move |db| {
+        credentials(db, &vault, "gitlab_token", ["gitlab_host"])
+    })
+    .await?;
     let token = token.ok_or("GitLab token not configured. Go to Settings → Integrations.")?;
     let host  = host.unwrap_or_else(|| "https://gitlab.com".to_string());
 
     let tasks = crate::integrations::fetch_gitlab_tasks(&token, &host).await?;
-    let db = db_state.lock().unwrap();
-    Ok(mark_imported(&db, tasks, "GitLab"))
+    with_db(&db_state, move |db| Ok::<_, String>(mark_imported(db, tasks, "GitLab"))).await
 }
 
 // ── Selective import commands ─────────────────────────────────────────────
@@ -150,7 +176,8 @@ pub async fn preview_sync_gitlab(
 /// Returns the count of newly inserted tasks (duplicates are silently skipped).
 #[tauri::command]
 pub async fn import_selected(
-    db_state:      State<'_, Arc<Mutex<Database>>>,
+    db_state:      State<'_, Arc<Database>>,
+    vault:         State<'_, Arc<Vault>>,
     source:        String,
     selected_ids:  Vec<String>,
     import_labels:   bool,
@@ -163,25 +190,20 @@ pub async fn import_selected(
     // Re-fetch so we have the full task data to insert
     let tasks: Vec<ExternalTask> = match source.as_str() {
         "GitHub" => {
-            let (token, repo) = {
-                let db = db_state.lock().unwrap();
-                (
-                    db.get_setting("github_token").unwrap_or(None),
-                    db.get_setting("github_repo").unwrap_or(None),
-                )
-            };
+            let vault = Arc::clone(&vault);
+            let (token, [repo]) = with_db(&db_state, move |db| {
+                credentials(db, &vault, "github_token", ["github_repo"])
+            })
+            .await?;
             let token = token.ok_or("GitHub token not configured.")?;
             crate::integrations::fetch_github_tasks(&token, repo.as_deref()).await?
         }
         "Jira" => {
-            let (domain, email, token) = {
-                let db = db_state.lock().unwrap();
-                (
-                    db.get_setting("jira_domain").unwrap_or(None),
-                    db.get_setting("jira_email").unwrap_or(None),
-                    db.get_setting("jira_token").unwrap_or(None),
-                )
-            };
+            let vault = Arc::clone(&vault);
+            let (token, [domain, email]) = with_db(&db_state, move |db| {
+                credentials(db, &vault, "jira_token", ["jira_domain", "jira_email"])
+            })
+            .await?;
             crate::integrations::fetch_jira_tasks(
                 &domain.ok_or("Jira domain not configured.")?,
                 &email.ok_or("Jira email not configured.")?,
@@ -189,13 +211,11 @@ pub async fn import_selected(
             ).await?
         }
         "GitLab" => {
-            let (token, host) = {
-                let db = db_state.lock().unwrap();
-                (
-                    db.get_setting("gitlab_token").unwrap_or(None),
-                    db.get_setting("gitlab_host").unwrap_or(None),
-                )
-            };
+            let vault = Arc::clone(&vault);
+            let (token, [host]) = with_db(&db_state, move |db| {
+                credentials(db, &vault, "gitlab_token", ["gitlab_host"])
+            })
+            .await?;
             let token = token.ok_or("GitLab token not configured.")?;
             let host  = host.unwrap_or_else(|| "https://gitlab.com".to_string());
             crate::integrations::fetch_gitlab_tasks(&token, &host).await?
@@ -203,25 +223,26 @@ pub async fn import_selected(
         other => return Err(format!("Unknown source: {}", other)),
     };
 
-    let db = db_state.lock().unwrap();
-
-    // Optionally create tags from labels
-    if import_labels {
-        let all_labels: Vec<String> = tasks
-            .iter()
-            .filter(|t| selected_ids.contains(&t.id))
-            .flat_map(|t| t.labels.clone())
-            .collect::<std::collections::HashSet<_>>()
-            .into_iter()
-            .collect();
+    with_db(&db_state, move |db| {
+        // Optionally create tags from labels
+        if import_labels {
+            let all_labels: Vec<String> = tasks
+                .iter()
+                .filter(|t| selected_ids.contains(&t.id))
+                .flat_map(|t| t.labels.clone())
+                .collect::<std::collections::HashSet<_>>()
+                .into_iter()
+                .collect();
 
-        for label in all_labels {
-            // create_tag uses INSERT OR IGNORE so existing tags are safe
-            let _ = db.create_tag(&label, None);
+            for label in all_labels {
+                // Matches existing tags ignoring case, so "Bug" reuses "bug"
+                let _ = db.find_or_create_tag(&label);
+            }
         }
-    }
 
-    Ok(save_by_ids(&db, tasks, &selected_ids, &source, import_projects))
+        Ok::<_, String>(save_by_ids(db, tasks, &selected_ids, &source, import_projects))
+    })
+    .await
 }
 
 // ── Legacy one-shot sync commands (kept for backwards compatibility) ───────
@@ -229,57 +250,50 @@ pub async fn import_selected(
 /// Sync GitHub issues — imports everything not already imported.
 #[tauri::command]
 pub async fn sync_github(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
+    vault:    State<'_, Arc<Vault>>,
 ) -> Result<usize, String> {
-    let (token, repo) = {
-        let db = db_state.lock().unwrap();
-        (
-            db.get_setting("github_token").unwrap_or(None),
-            db.get_setting("github_repo").unwrap_or(None),
-        )
-    };
+    let vault = Arc::clone(&vault);
+    let (token, [repo]) = with_db(&db_state, move |db| {
+        credentials(db, &vault, "github_token", ["github_repo"])
+    })
+    .await?;
     let token = token.ok_or("GitHub token not configured. Go to Settings → Integrations.")?;
     let tasks = crate::integrations::fetch_github_tasks(&token, repo.as_deref()).await?;
-    let db = db_state.lock().unwrap();
-    Ok(save_all(&db, tasks, "GitHub"))
+    with_db(&db_state, move |db| Ok::<_, String>(save_all(db, tasks, "GitHub"))).await
 }
 
 /// Sync Jira issues — imports everything not already imported.
 #[tauri::command]
 pub async fn sync_jira(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
+    vault:    State<'_, Arc<Vault>>,
 ) -> Result<usize, String> {
-    let (domain, email, token) = {
-        let db = db_state.lock().unwrap();
-        (
-            db.get_setting("jira_domain").unwrap_or(None),
-            db.get_setting("jira_email").unwrap_or(None),
-            db.get_setting("jira_token").unwrap_or(None),
-        )
-    };
+    let vault = Arc::clone(&vault);
+    let (token, [domain, email]) = with_db(&db_state, move |db| {
+        credentials(db, &vault, "jira_token", ["jira_domain", "jira_email"])
+    })
+    .await?;
     let domain = domain.ok_or("Jira domain not configured. Go to Settings → Integrations.")?;
     let email  = email.ok_or("Jira email not configured. Go to Settings → Integrations.")?;
     let token  = token.ok_or("Jira API token not configured. Go to Settings → Integrations.")?;
     let tasks = crate::integrations::fetch_jira_tasks(&domain, &email, &token).await?;
-    let db = db_state.lock().unwrap();
-    Ok(save_all(&db, tasks, "Jira"))
+    with_db(&db_state, move |db| Ok::<_, String>(save_all(db, tasks, "Jira"))).await
 }
 
 /// Sync GitLab issues — imports everything not already imported.
 #[tauri::command]
 pub async fn sync_gitlab(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
+    vault:    State<'_, Arc<Vault>>,
 ) -> Result<usize, String> {
-    let (token, host) = {
-        let db = db_state.lock().unwrap();
-        (
-            db.get_setting("gitlab_token").unwrap_or(None),
-            db.get_setting("gitlab_host").unwrap_or(None),
-        )
-    };
+    let vault = Arc::clone(&vault);
+    let (token, [host]) = with_db(&db_state, move |db| {
+        credentials(db, &vault, "gitlab_token", ["gitlab_host"])
+    })
+    .await?;
     let token = token.ok_or("GitLab token not configured. Go to Settings → Integrations.")?;
     let host  = host.unwrap_or_else(|| "https://gitlab.com".to_string());
     let tasks = crate::integrations::fetch_gitlab_tasks(&token, &host).await?;
-    let db = db_state.lock().unwrap();
-    Ok(save_all(&db, tasks, "GitLab"))
+    with_db(&db_state, move |db| Ok::<_, String>(save_all(db, tasks, "GitLab"))).await
 }
@@ -1,32 +1,83 @@
 // commands/tags.rs — Tag management
 
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use tauri::State;
 
+use crate::commands::with_db;
 use crate::database::{Database, Tag};
 
+/// Create a tag and return its id. Names are unique regardless of case, so
+/// an existing tag's id is returned for a name that is already taken.
 #[tauri::command]
 pub async fn create_tag(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
     name: String,
     color: Option<String>,
 ) -> Result<i64, String> {
-    db_state.lock().unwrap().create_tag(&name, color.as_deref()).map_err(|e| e.to_string())
+    let name = valid_name(name)?;
+    with_db(&db_state, move |db| db.create_tag(&name, color.as_deref())).await
 }
 
 #[tauri::command]
 pub async fn get_tags(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
 ) -> Result<Vec<Tag>, String> {
-    db_state.lock().unwrap().get_tags().map_err(|e| e.to_string())
+    with_db(&db_state, |db| db.get_tags()).await
+}
+
+/// Rename and/or recolour a tag.
+#[tauri::command]
+pub async fn update_tag(
+    db_state: State<'_, Arc<Database>>,
+    mut tag: Tag,
+) -> Result<(), String> {
+    tag.name = valid_name(tag.name)?;
+    let name = tag.name.clone();
+    let updated = with_db(&db_state, move |db| db.update_tag(&tag).map_err(not_found)).await?;
+    if !updated {
+        return Err(duplicate(&name));
+    }
+    Ok(())
+}
+
+/// Merge `source_ids` into `target_id`: every task tagged with a source tag
+/// gets the target tag instead, and the source tags are deleted. Atomic.
+#[tauri::command]
+pub async fn merge_tags(
+    db_state:   State<'_, Arc<Database>>,
+    source_ids: Vec<i64>,
+    target_id:  i64,
+) -> Result<(), String> {
+    with_db(&db_state, move |db| db.merge_tags(&source_ids, target_id).map_err(not_found)).await
 }
 
 /// Deletes a tag. The tag is automatically removed from all tasks
 /// via the SQLite ON DELETE CASCADE on task_tags.
 #[tauri::command]
 pub async fn delete_tag(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
     id: i64,
 ) -> Result<(), String> {
-    db_state.lock().unwrap().delete_tag(id).map_err(|e| e.to_string())
+    with_db(&db_state, move |db| db.delete_tag(id)).await
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+fn valid_name(name: String) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Tag name must not be empty.".into());
+    }
+    Ok(name.to_string())
+}
+
+fn duplicate(name: &str) -> String {
+    format!("A tag named \"{name}\" already exists.")
+}
+
+fn not_found(e: rusqlite::Error) -> String {
+    match e {
+        rusqlite::Error::QueryReturnedNoRows => "Tag not found.".to_string(),
+        e => e.to_string(),
+    }
 }
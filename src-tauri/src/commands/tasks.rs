@@ -1,59 +1,235 @@
 // commands/tasks.rs — Task CRUD operations
 
-use std::sync::{Arc, Mutex};
+use std::sync::Arc;
 use tauri::State;
 
-use crate::database::{Database, Task};
+use crate::commands::with_db;
+use crate::database::{
+    BatchOperation, BatchResult, Database, DependencyGraph, Task, TaskCompletion, TaskNode, TaskPage,
+    TaskQuery,
+};
 
-/// Create a new task and return its generated id.
+/// Create a new task and return its generated id. Its status must be a
+/// column of its project's workflow with room under the WIP limit.
 #[tauri::command]
 pub async fn create_task(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
     task: Task,
 ) -> Result<i64, String> {
-    db_state.lock().unwrap().create_task(task).map_err(|e| e.to_string())
+    validate(&task)?;
+    let status = task.status.clone();
+    let created = with_db(&db_state, move |db| db.create_task_checked(task)).await?;
+    created.map_err(|change| change.error(&status).unwrap_or_default())
 }
 
 /// Overwrite all mutable fields of an existing task.
 /// `completed_at` is handled in the database layer to preserve the original
 /// completion timestamp when a task is re-edited without re-completing it.
+/// Status changes are checked against the project's workflow (known status,
+/// allowed transition, WIP limit). Marking a recurring task done creates its
+/// next occurrence.
 #[tauri::command]
 pub async fn update_task(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
     task: Task,
 ) -> Result<(), String> {
-    db_state.lock().unwrap().update_task(task).map_err(|e| e.to_string())
+    validate(&task)?;
+    let status = task.status.clone();
+    let change = with_db(&db_state, move |db| db.update_task(task)).await?;
+    change.error(&status).map_or(Ok(()), Err)
 }
 
 /// Delete a task by id.
 #[tauri::command]
 pub async fn delete_task(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
     id: i64,
 ) -> Result<(), String> {
-    db_state.lock().unwrap().delete_task(id).map_err(|e| e.to_string())
+    with_db(&db_state, move |db| db.delete_task(id)).await
 }
 
-/// Return tasks with optional project / tag / status filters.
+/// Return tasks with optional project / tag / status filters. With
+/// `actionable` set, only tasks that aren't done or blocked are returned.
 #[tauri::command]
 pub async fn get_tasks(
-    db_state:      State<'_, Arc<Mutex<Database>>>,
+    db_state:      State<'_, Arc<Database>>,
     filter_project: Option<i64>,
     filter_tag:     Option<i64>,
     filter_status:  Option<String>,
+    actionable:     Option<bool>,
 ) -> Result<Vec<Task>, String> {
-    db_state
-        .lock()
-        .unwrap()
-        .get_tasks(filter_project, filter_tag, filter_status)
-        .map_err(|e| e.to_string())
+    let actionable = actionable.unwrap_or(false);
+    with_db(&db_state, move |db| db.get_tasks(filter_project, filter_tag, filter_status, actionable)).await
+}
+
+/// Structured task query: multi-value filters, sorting and cursor pagination.
+/// All values are bound as SQL parameters.
+#[tauri::command]
+pub async fn query_tasks(
+    db_state: State<'_, Arc<Database>>,
+    query: TaskQuery,
+) -> Result<TaskPage, String> {
+    with_db(&db_state, move |db| db.query_tasks(&query)).await?
 }
 
 /// Full-text search across task titles and descriptions.
 #[tauri::command]
 pub async fn search_tasks(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
     query: String,
 ) -> Result<Vec<Task>, String> {
-    db_state.lock().unwrap().search_tasks(&query).map_err(|e| e.to_string())
+    with_db(&db_state, move |db| db.search_tasks(&query)).await
+}
+
+// ── Ordering ──────────────────────────────────────────────────────────────
+
+/// Persist a new card order for one column (or one parent's subtasks):
+/// `ids` is the full list, top to bottom. All positions change together.
+#[tauri::command]
+pub async fn reorder_tasks(
+    db_state: State<'_, Arc<Database>>,
+    ids: Vec<i64>,
+) -> Result<(), String> {
+    with_db(&db_state, move |db| db.reorder_tasks(&ids).map_err(not_found)).await
+}
+
+/// Kanban drag-and-drop across columns: set the task's status and the
+/// destination column's order (`column_ids`, including the moved task)
+/// in one transaction. The move must be allowed by the project's workflow.
+#[tauri::command]
+pub async fn move_task_to_column(
+    db_state:   State<'_, Arc<Database>>,
+    id:         i64,
+    status:     String,
+    column_ids: Vec<i64>,
+) -> Result<(), String> {
+    if status.trim().is_empty() {
+        return Err("Status must not be empty.".into());
+    }
+    let change = with_db(&db_state, {
+        let status = status.clone();
+        move |db| db.move_task_to_column(id, &status, &column_ids).map_err(not_found)
+    })
+    .await?;
+    change.error(&status).map_or(Ok(()), Err)
+}
+
+// ── Batch operations ──────────────────────────────────────────────────────
+
+/// Apply one operation (status, project, priority, due date, tags or
+/// delete) to every task in `ids` in a single transaction. Returns one
+/// result per id; a failing id doesn't undo the others.
+#[tauri::command]
+pub async fn batch_update_tasks(
+    db_state:  State<'_, Arc<Database>>,
+    ids:       Vec<i64>,
+    operation: BatchOperation,
+) -> Result<Vec<BatchResult>, String> {
+    if let BatchOperation::SetStatus { status } = &operation {
+        if status.trim().is_empty() {
+            return Err("Status must not be empty.".into());
+        }
+    }
+    with_db(&db_state, move |db| {
+        db.batch_update_tasks(&ids, &operation).map_err(|e| match e {
+            rusqlite::Error::QueryReturnedNoRows => "Project or tag not found.".to_string(),
+            e => e.to_string(),
+        })
+    })
+    .await
+}
+
+// ── Subtasks ──────────────────────────────────────────────────────────────
+
+/// Load a task with all of its subtasks, each node carrying roll-ups of
+/// tracked time, estimates and progress. With no `root_id`, returns every
+/// top-level task as its own tree.
+#[tauri::command]
+pub async fn get_task_tree(
+    db_state: State<'_, Arc<Database>>,
+    root_id: Option<i64>,
+) -> Result<Vec<TaskNode>, String> {
+    with_db(&db_state, move |db| db.get_task_tree(root_id)).await
+}
+
+/// Move a task and its whole subtree under `parent_id` (or to the top level
+/// when `None`). Moving a task under itself or its own subtree is refused.
+#[tauri::command]
+pub async fn move_task(
+    db_state:  State<'_, Arc<Database>>,
+    id:        i64,
+    parent_id: Option<i64>,
+) -> Result<(), String> {
+    let moved = with_db(&db_state, move |db| db.move_task(id, parent_id).map_err(not_found)).await?;
+    if !moved {
+        return Err("A task can't be moved under itself or one of its subtasks.".into());
+    }
+    Ok(())
+}
+
+/// Complete a task. If it has open subtasks they are completed too when
+/// `complete_subtasks` is set; otherwise nothing changes and the open
+/// subtasks are returned so the UI can warn.
+#[tauri::command]
+pub async fn complete_task(
+    db_state: State<'_, Arc<Database>>,
+    id: i64,
+    complete_subtasks: bool,
+) -> Result<TaskCompletion, String> {
+    with_db(&db_state, move |db| db.complete_task(id, complete_subtasks).map_err(not_found)).await
+}
+
+// ── Dependencies ──────────────────────────────────────────────────────────
+
+/// Mark `task_id` as blocked by `blocked_by`. Links that would make a task
+/// wait on itself, directly or through other tasks, are refused.
+#[tauri::command]
+pub async fn add_task_dependency(
+    db_state:   State<'_, Arc<Database>>,
+    task_id:    i64,
+    blocked_by: i64,
+) -> Result<(), String> {
+    let added = with_db(&db_state, move |db| {
+        db.add_task_dependency(task_id, blocked_by).map_err(not_found)
+    })
+    .await?;
+    if !added {
+        return Err("A task can't depend on itself or on a task that depends on it.".into());
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn remove_task_dependency(
+    db_state:   State<'_, Arc<Database>>,
+    task_id:    i64,
+    blocked_by: i64,
+) -> Result<(), String> {
+    with_db(&db_state, move |db| db.remove_task_dependency(task_id, blocked_by)).await
+}
+
+/// The tasks of a project (every task with a link when `None`) together
+/// with their blocked-by links, including linked tasks from other projects.
+#[tauri::command]
+pub async fn get_dependency_graph(
+    db_state:   State<'_, Arc<Database>>,
+    project_id: Option<i64>,
+) -> Result<DependencyGraph, String> {
+    with_db(&db_state, move |db| db.get_dependency_graph(project_id)).await
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+fn validate(task: &Task) -> Result<(), String> {
+    match &task.recurrence {
+        Some(rule) => Database::validate_recurrence(rule),
+        None => Ok(()),
+    }
+}
+
+fn not_found(e: rusqlite::Error) -> String {
+    match e {
+        rusqlite::Error::QueryReturnedNoRows => "Task not found.".to_string(),
+        e => e.to_string(),
+    }
 }
@@ -0,0 +1,109 @@
+// commands/templates.rs — Task templates and creating tasks from them
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use tauri::State;
+
+use crate::commands::with_db;
+use crate::database::{Database, TaskTemplate};
+
+/// Save a new template and return its id.
+#[tauri::command]
+pub async fn create_template(
+    db_state: State<'_, Arc<Database>>,
+    mut template: TaskTemplate,
+) -> Result<i64, String> {
+    Database::validate_template(&mut template)?;
+    with_db(&db_state, move |db| db.create_template(&template)).await
+}
+
+/// All templates, by name.
+#[tauri::command]
+pub async fn get_templates(
+    db_state: State<'_, Arc<Database>>,
+) -> Result<Vec<TaskTemplate>, String> {
+    with_db(&db_state, |db| db.get_templates()).await
+}
+
+/// Overwrite a template's fields, tags and subtasks.
+#[tauri::command]
+pub async fn update_template(
+    db_state: State<'_, Arc<Database>>,
+    mut template: TaskTemplate,
+) -> Result<(), String> {
+    Database::validate_template(&mut template)?;
+    with_db(&db_state, move |db| db.update_template(&template).map_err(not_found)).await
+}
+
+#[tauri::command]
+pub async fn delete_template(
+    db_state: State<'_, Arc<Database>>,
+    id: i64,
+) -> Result<(), String> {
+    with_db(&db_state, move |db| db.delete_template(id)).await
+}
+
+/// Placeholders the caller has to fill in before instantiating, e.g. `["version"]`.
+/// `{date}` (YYYY-MM-DD) and `{week}` (YYYY-Www) are filled in automatically.
+#[tauri::command]
+pub async fn get_template_placeholders(
+    db_state: State<'_, Arc<Database>>,
+    id: i64,
+) -> Result<Vec<String>, String> {
+    let template = load(&db_state, id).await?;
+    Ok(Database::template_placeholders(&template))
+}
+
+/// Create a task with the template's subtasks, replacing `{name}` in titles
+/// and descriptions with `values[name]`. Returns the new root task's id.
+#[tauri::command]
+pub async fn instantiate_template(
+    db_state: State<'_, Arc<Database>>,
+    id:       i64,
+    values:   Option<HashMap<String, String>>,
+    due_date: Option<i64>,
+) -> Result<i64, String> {
+    let values: HashMap<String, String> = values
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(name, value)| (name, value.trim().to_string()))
+        .filter(|(_, value)| !value.is_empty())
+        .collect();
+
+    let template = load(&db_state, id).await?;
+    let missing: Vec<String> = Database::template_placeholders(&template)
+        .into_iter()
+        .filter(|name| !values.contains_key(name))
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Missing template values: {}.", missing.join(", ")));
+    }
+
+    let created = with_db(&db_state, move |db| {
+        db.instantiate_template(id, &values, due_date).map_err(not_found)
+    })
+    .await?;
+    match created {
+        Ok(root_id) => Ok(root_id),
+        Err(change) => {
+            let project_id = template.project_id;
+            let workflow = with_db(&db_state, move |db| db.get_workflow(project_id)).await?;
+            Err(change.error(workflow.initial_status()).unwrap_or_default())
+        }
+    }
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+async fn load(db_state: &Arc<Database>, id: i64) -> Result<TaskTemplate, String> {
+    with_db(db_state, move |db| db.get_template(id))
+        .await?
+        .ok_or_else(|| "Template not found.".to_string())
+}
+
+fn not_found(e: rusqlite::Error) -> String {
+    match e {
+        rusqlite::Error::QueryReturnedNoRows => "Template not found.".to_string(),
+        e => e.to_string(),
+    }
+}
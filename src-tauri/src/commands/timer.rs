@@ -10,8 +10,9 @@ use tauri::{AppHandle, State};
 use tauri::Emitter;
 use tauri_plugin_notification::NotificationExt;
 
-use crate::database::Database;
-use crate::commands::notifications::bool_setting;
+use crate::database::{BudgetAlert, BudgetPeriod, Database};
+use crate::commands::notifications::live_settings;
+use crate::commands::with_db;
 
 // ── TimerState ────────────────────────────────────────────────────────────
 
@@ -76,7 +77,7 @@ impl Default for TimerState {
 #[tauri::command]
 pub async fn start_pomodoro(
     state:    State<'_, Arc<Mutex<TimerState>>>,
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
     handle:   AppHandle,
     task_name: String,
     duration_minutes: Option<u64>,
@@ -88,22 +89,24 @@ pub async fn start_pomodoro(
     notif_break_recommend: Option<String>,
 ) -> Result<(), String> {
     // Finalize the previous session if one was active
-    let previous_session = {
+    let (previous_session, interrupted) = {
         let t = state.lock().unwrap();
         if t.task_active {
             let name = t.active_task_name.clone().unwrap_or_default();
             let elapsed = if !name.is_empty() {
                 t.session_duration.saturating_sub(t.remaining)
             } else { 0 };
-            Some((name, elapsed, t.phase))
+            (Some((name, elapsed, t.phase)), t.phase == 0)
         } else {
-            None
+            (None, false)
         }
     };
 
     if let Some((old_name, elapsed, phase)) = previous_session {
         if elapsed > 0 {
-            db_state.lock().unwrap().log_session_complete(&old_name, elapsed, phase).unwrap_or(());
+            with_db(&db_state, move |db| db.log_session_complete(&old_name, elapsed, phase))
+                .await
+                .unwrap_or(());
         }
     }
 
@@ -133,18 +136,17 @@ pub async fn start_pomodoro(
         !was_running
     };
 
-    db_state.lock().unwrap().log_action(&task_name, "start", 0, 0).unwrap();
+    let name = task_name.clone();
+    with_db(&db_state, move |db| db.log_work_start(&name, interrupted)).await?;
     if should_spawn_loop {
         spawn_tick_loop(Arc::clone(&*state), Arc::clone(&*db_state), handle.clone());
     }
 
     // Notify timer-started if the setting is on
     {
-        let db  = Arc::clone(&*db_state);
         let msg = state.lock().unwrap().notif_session_started.clone();
-        if bool_setting(&db, "notifications_enabled", true)
-            && bool_setting(&db, "notify_on_timer_start", true)
-        {
+        let settings = live_settings(&handle);
+        if settings.notifications_enabled && settings.notify_on_timer_start {
             let _ = handle
                 .notification()
                 .builder()
@@ -164,7 +166,7 @@ pub async fn start_pomodoro(
 #[tauri::command]
 pub async fn start_break(
     state:    State<'_, Arc<Mutex<TimerState>>>,
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
     handle:   AppHandle,
     duration_minutes: u64,
     phase: u8,
@@ -194,7 +196,9 @@ pub async fn start_break(
         !was_running
     };
 
-    db_state.lock().unwrap().log_action("Break", "start", 0, phase).unwrap_or(());
+    with_db(&db_state, move |db| db.log_action("Break", "start", 0, phase))
+        .await
+        .unwrap_or(());
     if should_spawn_loop {
         spawn_tick_loop(Arc::clone(&*state), Arc::clone(&*db_state), handle.clone());
     }
@@ -210,25 +214,31 @@ pub async fn start_break(
 
 /// Toggle pause/resume. Also restarts the tick loop if we're resuming.
 #[tauri::command]
-pub fn pause_timer(
+pub async fn pause_timer(
     state:    State<'_, Arc<Mutex<TimerState>>>,
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
     handle:   AppHandle,
 ) -> Result<(), String> {
-    let should_resume = {
+    let (should_resume, action, task_name, phase) = {
         let mut timer = state.lock().unwrap();
         let action = if timer.paused { "resume" } else { "pause" };
         let task_name = timer.active_task_name.clone().unwrap_or_default();
-        db_state.lock().unwrap().log_action(&task_name, action, 0, timer.phase).unwrap_or(());
+        let phase = timer.phase;
 
         if !timer.task_active && timer.remaining > 0 {
             timer.task_active  = true;
             timer.last_activity = now_secs();
         }
         timer.paused = !timer.paused;
-        !timer.paused && timer.task_active && timer.remaining > 0 && !timer.loop_running
+        let should_resume =
+            !timer.paused && timer.task_active && timer.remaining > 0 && !timer.loop_running;
+        (should_resume, action, task_name, phase)
     };
 
+    with_db(&db_state, move |db| db.log_action(&task_name, action, 0, phase))
+        .await
+        .unwrap_or(());
+
     if should_resume {
         state.lock().unwrap().loop_running = true;
         spawn_tick_loop(Arc::clone(&*state), Arc::clone(&*db_state), handle.clone());
@@ -248,23 +258,31 @@ pub fn get_timer(state: State<'_, Arc<Mutex<TimerState>>>) -> Result<TimerState,
 }
 
 /// Stop the timer, log any elapsed time, and reset to the last used duration.
+/// An active session also gets a "reset" row, so it counts as ended early.
 #[tauri::command]
-pub fn reset_timer(
+pub async fn reset_timer(
     state:    State<'_, Arc<Mutex<TimerState>>>,
-    db_state: State<'_, Arc<Mutex<Database>>>,
+    db_state: State<'_, Arc<Database>>,
     handle:   AppHandle,
 ) -> Result<(), String> {
-    let (elapsed, phase, task_name) = {
+    let (active, elapsed, phase, task_name) = {
         let t = state.lock().unwrap();
         let name = t.active_task_name.clone().unwrap_or_default();
         let elapsed = if t.task_active && !name.is_empty() {
             t.session_duration.saturating_sub(t.remaining)
         } else { 0 };
-        (elapsed, t.phase, name)
+        (t.task_active, elapsed, t.phase, name)
     };
 
-    if elapsed > 0 {
-        db_state.lock().unwrap().log_session_complete(&task_name, elapsed, phase).unwrap_or(());
+    if active {
+        with_db(&db_state, move |db| {
+            if elapsed > 0 {
+                db.log_session_complete(&task_name, elapsed, phase)?;
+            }
+            db.log_action(&task_name, "reset", 0, phase)
+        })
+        .await
+        .unwrap_or(());
     }
 
     let mut t = state.lock().unwrap();
@@ -294,11 +312,11 @@ pub fn record_activity(state: State<'_, Arc<Mutex<TimerState>>>) -> Result<(), S
 
 /// Return the N most recently tracked task names (for autocomplete).
 #[tauri::command]
-pub fn get_unique_task_names(
-    db_state: State<'_, Arc<Mutex<Database>>>,
+pub async fn get_unique_task_names(
+    db_state: State<'_, Arc<Database>>,
     limit: i64,
 ) -> Result<Vec<String>, String> {
-    db_state.lock().unwrap().get_unique_task_names(limit).map_err(|e| e.to_string())
+    with_db(&db_state, move |db| db.get_unique_task_names(limit)).await
 }
 
 // ── Private helpers ───────────────────────────────────────────────────────
@@ -310,42 +328,66 @@ fn now_secs() -> u64 {
         .as_secs()
 }
 
+/// e.g. "Acme website has used 75% of its 40 h budget this month (30.2 h)."
+fn budget_alert_body(alert: &BudgetAlert) -> String {
+    let status = &alert.status;
+    let period = match status.period {
+        BudgetPeriod::Total   => "",
+        BudgetPeriod::Monthly => " this month",
+    };
+    format!(
+        "{} has used {}% of its {} h budget{} ({:.1} h).",
+        status.project_name,
+        alert.threshold,
+        status.budget_minutes as f64 / 60.0,
+        period,
+        status.consumed_seconds as f64 / 3600.0,
+    )
+}
+
 /// Spawns a one-second-tick background loop in Tauri's async runtime.
 /// The loop stops automatically when the timer is paused, complete, or idle.
 fn spawn_tick_loop(
     state:    Arc<Mutex<TimerState>>,
-    db:       Arc<Mutex<Database>>,
+    db:       Arc<Database>,
     handle:   AppHandle,
 ) {
     tauri::async_runtime::spawn(async move {
         let mut interval = tokio::time::interval(Duration::from_secs(1));
+        let mut idle_pause = None;
         loop {
             interval.tick().await;
 
-            let mut timer = state.lock().unwrap();
+            // Advance the timer under the lock. The guard must be released
+            // before the completion path below awaits on the database.
+            let (phase, duration, task_name, session_count) = {
+                let mut timer = state.lock().unwrap();
 
-            // Exit conditions
-            if timer.paused || !timer.task_active {
-                timer.loop_running = false;
-                break;
-            }
+                // Exit conditions
+                if timer.paused || !timer.task_active {
+                    timer.loop_running = false;
+                    break;
+                }
 
-            // Auto-pause on 2 minutes of inactivity
-            if now_secs() - timer.last_activity > 120 {
-                timer.paused       = true;
-                timer.loop_running = false;
-                let payload = timer.clone();
-                drop(timer);
-                let _ = handle.emit("timer-tick", payload);
-                break;
-            }
+                // Auto-pause on 2 minutes of inactivity
+                if now_secs() - timer.last_activity > 120 {
+                    timer.paused       = true;
+                    timer.loop_running = false;
+                    let payload = timer.clone();
+                    drop(timer);
+                    let _ = handle.emit("timer-tick", &payload);
+                    idle_pause = Some((payload.active_task_name.unwrap_or_default(), payload.phase));
+                    break;
+                }
+
+                if timer.remaining > 0 {
+                    timer.remaining -= 1;
+                    let payload = timer.clone();
+                    drop(timer);
+                    let _ = handle.emit("timer-tick", payload);
+                    continue;
+                }
 
-            if timer.remaining > 0 {
-                timer.remaining -= 1;
-                let payload = timer.clone();
-                drop(timer);
-                let _ = handle.emit("timer-tick", payload);
-            } else {
                 // Session complete
                 let phase    = timer.phase;
                 let duration = timer.session_duration;
@@ -358,56 +400,82 @@ fn spawn_tick_loop(
                 if phase == 0 {
                     timer.pomodoro_session_count += 1;
                 }
-                let session_count = timer.pomodoro_session_count;
-
-                drop(timer);
-
-                db.lock().unwrap().log_session_complete(&task_name, duration, phase).unwrap_or(());
-
-                // ── Notifications ────────────────────────────────────────────
-                let db_ref = Arc::clone(&db);
-                let notif_enabled = bool_setting(&db_ref, "notifications_enabled", true);
-
-                if notif_enabled {
-                    // Timer-end notification
-                    if bool_setting(&db_ref, "notify_on_timer_end", true) {
-                        let body = {
-                            let t = state.lock().unwrap();
-                            if phase == 0 {
-                                t.notif_session_complete.clone()
-                            } else {
-                                t.notif_break_over.clone()
-                            }
-                        };
-                        let _ = handle
-                            .notification()
-                            .builder()
-                            .title("Code Chrono")
-                            .body(&body)
-                            .show();
-                    }
+                (phase, duration, task_name, timer.pomodoro_session_count)
+            };
+
+            // Log off the runtime so a slow disk can't delay the next tick
+            let logged_name = task_name.clone();
+            let _ = with_db(&db, move |db| db.log_session_complete(&logged_name, duration, phase)).await;
+
+            let settings = live_settings(&handle);
+
+            // Budget thresholds are recorded even with notifications off,
+            // so turning them back on doesn't replay old alerts.
+            let budget_alerts = if phase == 0 && !task_name.is_empty() {
+                let thresholds = settings.budget_alert_thresholds.clone();
+                with_db(&db, move |db| db.take_budget_alerts(&task_name, &thresholds))
+                    .await
+                    .unwrap_or_default()
+            } else {
+                Vec::new()
+            };
+
+            // ── Notifications ────────────────────────────────────────────
+            if settings.notifications_enabled {
+                // Timer-end notification
+                if settings.notify_on_timer_end {
+                    let body = {
+                        let t = state.lock().unwrap();
+                        if phase == 0 {
+                            t.notif_session_complete.clone()
+                        } else {
+                            t.notif_break_over.clone()
+                        }
+                    };
+                    let _ = handle
+                        .notification()
+                        .builder()
+                        .title("Code Chrono")
+                        .body(&body)
+                        .show();
+                }
 
-                    // Break recommendation after 4 consecutive work sessions
-                    if phase == 0
-                        && session_count > 0
-                        && session_count % 4 == 0
-                        && bool_setting(&db_ref, "notify_break_recommend", true)
-                    {
-                        let (title, body) = {
-                            let t = state.lock().unwrap();
-                            (t.notif_break_title.clone(), t.notif_break_recommend.clone())
-                        };
+                // Break recommendation after 4 consecutive work sessions
+                if phase == 0
+                    && session_count > 0
+                    && session_count % 4 == 0
+                    && settings.notify_break_recommend
+                {
+                    let (title, body) = {
+                        let t = state.lock().unwrap();
+                        (t.notif_break_title.clone(), t.notif_break_recommend.clone())
+                    };
+                    let _ = handle
+                        .notification()
+                        .builder()
+                        .title(&title)
+                        .body(&body)
+                        .show();
+                }
+
+                if settings.notify_budget_alerts {
+                    for alert in &budget_alerts {
                         let _ = handle
                             .notification()
                             .builder()
-                            .title(&title)
-                            .body(&body)
+                            .title("Budget alert")
+                            .body(budget_alert_body(alert))
                             .show();
                     }
                 }
-
-                break;
             }
+
+            break;
+        }
+
+        // Logged like a manual pause, so the idle time isn't counted as focus
+        if let Some((name, phase)) = idle_pause {
+            let _ = with_db(&db, move |db| db.log_action(&name, "pause", 0, phase)).await;
         }
     });
 }
@@ -0,0 +1,162 @@
+// database/budgets.rs
+//
+// Project time budgets. Consumption is the elapsed time of completed work
+// sessions whose task is in the project or one of its sub-projects, with
+// sessions linked to tasks as stats and billing do (see
+// `sessions::MATCHED_SESSIONS`). Each threshold alert is
+// recorded in `budget_alerts` per period ("total" or "YYYY-MM"), so it
+// fires once per budget period.
+
+use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension, Result};
+
+use super::calendar::Calendar;
+use super::models::{BudgetAlert, BudgetPeriod, BudgetStatus};
+use super::sessions::MATCHED_SESSIONS;
+
+pub(super) fn period_to_column(period: BudgetPeriod) -> &'static str {
+    match period {
+        BudgetPeriod::Total   => "total",
+        BudgetPeriod::Monthly => "monthly",
+    }
+}
+
+pub(super) fn period_from_column(raw: Option<String>) -> BudgetPeriod {
+    match raw.as_deref() {
+        Some("monthly") => BudgetPeriod::Monthly,
+        _ => BudgetPeriod::Total,
+    }
+}
+
+/// Budget status of every non-archived project that has a budget, or of
+/// `project_id` alone (archived or not). Monthly budgets cover the current
+/// month of `calendar`.
+pub fn status(conn: &Connection, calendar: &Calendar, project_id: Option<i64>) -> Result<Vec<BudgetStatus>> {
+    let now = Utc::now().timestamp();
+    let mut stmt = conn.prepare(
+        "SELECT id FROM projects
+         WHERE budget_minutes IS NOT NULL
+           AND (?1 = id OR (?1 IS NULL AND archived = 0))
+         ORDER BY name",
+    )?;
+    let ids: Vec<i64> = stmt.query_map([project_id], |row| row.get(0))?.filter_map(Result::ok).collect();
+
+    let mut out = Vec::with_capacity(ids.len());
+    for id in ids {
+        if let Some(status) = load(conn, calendar, id, now)? {
+            out.push(status);
+        }
+    }
+    Ok(out)
+}
+
+/// Record and return the thresholds newly crossed by projects that time
+/// logged on `task_name` counts towards (its project and that project's
+/// ancestors). When a single session crosses several thresholds at once
+/// they are all recorded, but only the highest is returned.
+pub fn take_alerts(
+    conn:       &Connection,
+    calendar:   &Calendar,
+    task_name:  &str,
+    thresholds: &[u32],
+) -> Result<Vec<BudgetAlert>> {
+    let now = Utc::now().timestamp();
+    let tx = conn.unchecked_transaction()?;
+    let mut stmt = tx.prepare(
+        "WITH RECURSIVE up(id) AS (
+             SELECT project_id FROM tasks WHERE title = ?1 AND project_id IS NOT NULL
+             UNION
+             SELECT p.parent_id FROM projects p JOIN up ON p.id = up.id
+             WHERE p.parent_id IS NOT NULL
+         )
+         SELECT p.id FROM projects p JOIN up ON up.id = p.id
+         WHERE p.budget_minutes IS NOT NULL AND p.archived = 0",
+    )?;
+    let ids: Vec<i64> = stmt.query_map([task_name], |row| row.get(0))?.filter_map(Result::ok).collect();
+    drop(stmt);
+
+    let mut thresholds = thresholds.to_vec();
+    thresholds.sort_unstable();
+    thresholds.dedup();
+
+    let mut alerts = Vec::new();
+    for id in ids {
+        let Some(status) = load(&tx, calendar, id, now)? else { continue };
+        let period = period_key(calendar, &status);
+        let mut crossed = None;
+        for &threshold in thresholds.iter().filter(|t| status.percent_used >= f64::from(**t)) {
+            let inserted = tx.execute(
+                "INSERT OR IGNORE INTO budget_alerts (project_id, period, threshold, sent_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                (id, &period, threshold, now),
+            )?;
+            if inserted > 0 {
+                crossed = Some(threshold);
+            }
+        }
+        if let Some(threshold) = crossed {
+            alerts.push(BudgetAlert { status, threshold });
+        }
+    }
+    tx.commit()?;
+    Ok(alerts)
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+fn load(conn: &Connection, calendar: &Calendar, id: i64, now: i64) -> Result<Option<BudgetStatus>> {
+    let row = conn
+        .query_row(
+            "SELECT name, budget_minutes, budget_period FROM projects
+             WHERE id = ?1 AND budget_minutes IS NOT NULL",
+            [id],
+            |row| Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?, period_from_column(row.get(2)?))),
+        )
+        .optional()?;
+    let Some((project_name, budget_minutes, period)) = row else { return Ok(None) };
+
+    let period_start = match period {
+        BudgetPeriod::Total   => None,
+        BudgetPeriod::Monthly => Some(calendar.day_start(calendar.month(now))),
+    };
+    let consumed_seconds: i64 = conn.query_row(
+        &format!(
+            "{MATCHED_SESSIONS},
+             sub(id) AS (
+                 SELECT ?3
+                 UNION
+                 SELECT p.id FROM projects p JOIN sub ON p.parent_id = sub.id
+             )
+             SELECT COALESCE(SUM(m.elapsed), 0)
+             FROM   matched m
+             JOIN   tasks t ON t.id = m.task_id
+             WHERE  t.project_id IN (SELECT id FROM sub)"
+        ),
+        (period_start.unwrap_or(i64::MIN), i64::MAX, id),
+        |row| row.get(0),
+    )?;
+
+    let budget_seconds = budget_minutes * 60;
+    let percent_used = if budget_seconds > 0 {
+        consumed_seconds as f64 * 100.0 / budget_seconds as f64
+    } else {
+        0.0
+    };
+    Ok(Some(BudgetStatus {
+        project_id: id,
+        project_name,
+        budget_minutes,
+        period,
+        period_start,
+        consumed_seconds,
+        remaining_seconds: budget_seconds - consumed_seconds,
+        percent_used,
+    }))
+}
+
+fn period_key(calendar: &Calendar, status: &BudgetStatus) -> String {
+    match status.period_start {
+        Some(start) => calendar.month(start).format("%Y-%m").to_string(),
+        None => "total".to_string(),
+    }
+}
@@ -0,0 +1,257 @@
+// database/calendar.rs
+//
+// How timestamps are bucketed into days, weeks and months: in the configured
+// timezone (or the system zone), with a day that starts at the rollover hour
+// and weeks that start on the configured weekday.
+//
+// The same rules are registered on every connection as SQL functions, so
+// queries can group by them directly:
+//   local_day(ts)   → 'YYYY-MM-DD', the day `ts` counts towards
+//   local_week(ts)  → 'YYYY-MM-DD', the first day of that day's week
+//   local_month(ts) → 'YYYY-MM'
+// They read the `Calendar` shared with `Database`, which is refreshed
+// whenever the settings are saved.
+
+use std::sync::{Arc, PoisonError, RwLock};
+
+use chrono::{
+    DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc,
+};
+use chrono_tz::Tz;
+use rusqlite::functions::FunctionFlags;
+use rusqlite::{Connection, Result};
+
+use super::models::Settings;
+
+/// The calendar shared by `Database` and its connections' SQL functions.
+pub type SharedCalendar = Arc<RwLock<Calendar>>;
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+enum Zone {
+    #[default]
+    System,
+    Named(Tz),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Default)]
+pub struct Calendar {
+    zone:          Zone,
+    rollover_hour: u32,
+    /// 0 = Monday … 6 = Sunday
+    week_start:    u32,
+}
+
+impl Calendar {
+    /// An unknown timezone falls back to the system zone; `Settings::validate`
+    /// rejects one before it is saved.
+    pub fn from_settings(settings: &Settings) -> Self {
+        let zone = match settings.timezone.trim() {
+            "" => Zone::System,
+            name => name.parse().map_or(Zone::System, Zone::Named),
+        };
+        Calendar {
+            zone,
+            rollover_hour: settings.day_rollover_hour.min(23),
+            week_start:    settings.week_start.min(6),
+        }
+    }
+
+    /// The day `ts` counts towards: its local date, or the day before when
+    /// it is earlier than the rollover hour.
+    pub fn day(&self, ts: i64) -> NaiveDate {
+        (self.local(ts) - Duration::hours(i64::from(self.rollover_hour))).date()
+    }
+
+    /// The first day of the week `ts` counts towards.
+    pub fn week(&self, ts: i64) -> NaiveDate {
+        let day = self.day(ts);
+        day - Duration::days(i64::from(self.weekday_index(day)))
+    }
+
+    /// The first day of the month `ts` counts towards.
+    pub fn month(&self, ts: i64) -> NaiveDate {
+        let day = self.day(ts);
+        day.with_day(1).unwrap_or(day)
+    }
+
+    /// The instant `day` begins, i.e. its rollover hour in local time. When
+    /// that hour is skipped by a DST change, the first instant after it.
+    pub fn day_start(&self, day: NaiveDate) -> i64 {
+        let start = day.and_hms_opt(self.rollover_hour, 0, 0).unwrap_or_default();
+        (0..3)
+            .find_map(|h| self.timestamp(start + Duration::hours(h)))
+            .unwrap_or_else(|| start.and_utc().timestamp())
+    }
+
+    /// The local clock hour of `ts` (0–23).
+    pub fn hour(&self, ts: i64) -> u32 {
+        self.local(ts).hour()
+    }
+
+    /// The instant the local clock hour containing `ts` ends.
+    pub fn hour_end(&self, ts: i64) -> i64 {
+        let local = self.local(ts);
+        ts + 3600 - i64::from(local.minute() * 60 + local.second())
+    }
+
+    /// The first day of the week, 0 = Monday … 6 = Sunday.
+    pub fn week_start(&self) -> u32 {
+        self.week_start
+    }
+
+    /// Weekday of `day` counted from the configured first day (0–6).
+    pub fn weekday_index(&self, day: NaiveDate) -> u32 {
+        (day.weekday().num_days_from_monday() + 7 - self.week_start) % 7
+    }
+
+    /// The local date and time of `ts` in the calendar's timezone, ignoring
+    /// the rollover hour.
+    pub fn local(&self, ts: i64) -> NaiveDateTime {
+        let utc = DateTime::<Utc>::from_timestamp(ts, 0).unwrap_or_default();
+        match self.zone {
+            Zone::System    => utc.with_timezone(&Local).naive_local(),
+            Zone::Named(tz) => utc.with_timezone(&tz).naive_local(),
+        }
+    }
+
+    /// The instant of the local time `local`; the earlier one when a DST
+    /// change repeats it, `None` when it skips it.
+    pub fn timestamp(&self, local: NaiveDateTime) -> Option<i64> {
+        match self.zone {
+            Zone::System    => Local.from_local_datetime(&local).earliest().map(|t| t.timestamp()),
+            Zone::Named(tz) => tz.from_local_datetime(&local).earliest().map(|t| t.timestamp()),
+        }
+    }
+}
+
+/// Read the shared calendar.
+pub fn current(shared: &SharedCalendar) -> Calendar {
+    *shared.read().unwrap_or_else(PoisonError::into_inner)
+}
+
+/// Replace the shared calendar, e.g. after the settings changed.
+pub fn set(shared: &SharedCalendar, calendar: Calendar) {
+    *shared.write().unwrap_or_else(PoisonError::into_inner) = calendar;
+}
+
+/// Register the calendar SQL functions on `conn`. They aren't deterministic:
+/// their result changes with the settings.
+pub fn register(conn: &Connection, shared: &SharedCalendar) -> Result<()> {
+    let flags = FunctionFlags::SQLITE_UTF8;
+
+    let cal = Arc::clone(shared);
+    conn.create_scalar_function("local_day", 1, flags, move |ctx| {
+        let ts: Option<i64> = ctx.get(0)?;
+        Ok(ts.map(|ts| current(&cal).day(ts).format("%Y-%m-%d").to_string()))
+    })?;
+
+    let cal = Arc::clone(shared);
+    conn.create_scalar_function("local_week", 1, flags, move |ctx| {
+        let ts: Option<i64> = ctx.get(0)?;
+        Ok(ts.map(|ts| current(&cal).week(ts).format("%Y-%m-%d").to_string()))
+    })?;
+
+    let cal = Arc::clone(shared);
+    conn.create_scalar_function("local_month", 1, flags, move |ctx| {
+        let ts: Option<i64> = ctx.get(0)?;
+        Ok(ts.map(|ts| current(&cal).month(ts).format("%Y-%m").to_string()))
+    })?;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn calendar(timezone: &str, rollover_hour: u32, week_start: u32) -> Calendar {
+        Calendar::from_settings(&Settings {
+            timezone: timezone.into(),
+            day_rollover_hour: rollover_hour,
+            week_start,
+            ..Settings::default()
+        })
+    }
+
+    /// A UTC time ("YYYY-MM-DD HH:MM") as a timestamp.
+    fn utc(time: &str) -> i64 {
+        NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap().and_utc().timestamp()
+    }
+
+    fn date(day: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(day, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn day_applies_timezone_and_rollover() {
+        // (timezone, rollover hour, UTC time, day it counts towards)
+        let cases = [
+            ("UTC",           0, "2026-03-10 23:59", "2026-03-10"),
+            ("UTC",           4, "2026-03-10 03:59", "2026-03-09"),
+            ("UTC",           4, "2026-03-10 04:00", "2026-03-10"),
+            ("Asia/Tokyo",    0, "2026-03-10 15:00", "2026-03-11"),
+            ("Asia/Tokyo",    4, "2026-03-10 18:30", "2026-03-10"),
+            // Month and year ends move with the rollover too
+            ("UTC",           4, "2026-04-01 02:00", "2026-03-31"),
+            ("UTC",           4, "2027-01-01 03:00", "2026-12-31"),
+            // 01:30 local in winter (UTC+1) and in summer (UTC+2)
+            ("Europe/Berlin", 2, "2026-03-01 00:30", "2026-02-28"),
+            ("Europe/Berlin", 2, "2026-07-01 23:30", "2026-07-01"),
+        ];
+        for (timezone, rollover, time, day) in cases {
+            let cal = calendar(timezone, rollover, 0);
+            assert_eq!(cal.day(utc(time)), date(day), "{time} in {timezone}, rollover {rollover}");
+        }
+    }
+
+    #[test]
+    fn week_starts_on_the_configured_day() {
+        // 2026-03-10 is a Tuesday. (week start, UTC time, first day of its week)
+        let cases = [
+            (0, "2026-03-10 12:00", "2026-03-09"),
+            (6, "2026-03-10 12:00", "2026-03-08"),
+            (1, "2026-03-10 12:00", "2026-03-10"),
+            (2, "2026-03-10 12:00", "2026-03-04"),
+            // Sunday night before the rollover still belongs to the old week
+            (0, "2026-03-16 02:00", "2026-03-09"),
+            (0, "2026-03-16 05:00", "2026-03-16"),
+            // Weeks run across month and year ends
+            (0, "2027-01-01 12:00", "2026-12-28"),
+        ];
+        for (week_start, time, first) in cases {
+            let cal = calendar("UTC", 4, week_start);
+            assert_eq!(cal.week(utc(time)), date(first), "{time}, week start {week_start}");
+        }
+    }
+
+    #[test]
+    fn day_start_and_hour_end_across_dst() {
+        let berlin = calendar("Europe/Berlin", 2, 0);
+        // (day, UTC instant the day begins at its 02:00 rollover)
+        let starts = [
+            ("2026-01-15", "2026-01-15 01:00"),
+            ("2026-07-15", "2026-07-15 00:00"),
+            // 02:00 is skipped on 29 March; the day begins at 03:00 CEST
+            ("2026-03-29", "2026-03-29 01:00"),
+            // 02:00 happens twice on 25 October; the first one counts
+            ("2026-10-25", "2026-10-25 00:00"),
+        ];
+        for (day, start) in starts {
+            assert_eq!(berlin.day_start(date(day)), utc(start), "{day}");
+        }
+
+        // (timezone, UTC time, UTC end of its local hour)
+        let hours = [
+            ("UTC",           "2026-03-10 10:00", "2026-03-10 11:00"),
+            ("UTC",           "2026-03-10 10:59", "2026-03-10 11:00"),
+            ("Europe/Berlin", "2026-03-29 00:30", "2026-03-29 01:00"),
+            ("Europe/Berlin", "2026-10-25 00:15", "2026-10-25 01:00"),
+            // Half-hour offset: local hours end at :30 UTC
+            ("Asia/Kolkata",  "2026-03-10 10:00", "2026-03-10 10:30"),
+        ];
+        for (timezone, time, end) in hours {
+            let cal = calendar(timezone, 0, 0);
+            assert_eq!(cal.hour_end(utc(time)), utc(end), "{time} in {timezone}");
+        }
+    }
+}
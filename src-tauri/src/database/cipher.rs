@@ -0,0 +1,66 @@
+// database/cipher.rs
+//
+// SQLCipher helpers for optional whole-database encryption.
+//
+// An encrypted database is unreadable until its passphrase is applied with
+// `PRAGMA key`, which must be the first statement on every connection.
+// Enabling, changing and removing encryption all work the same way: export
+// the live database into a fresh file with the target key (empty = plain),
+// then swap that file in place of the original.
+
+use std::path::Path;
+
+use rusqlite::{Connection, Error, ErrorCode, Result};
+
+/// Apply `passphrase` to a freshly opened connection.
+pub fn apply_key(conn: &Connection, passphrase: &str) -> Result<()> {
+    conn.pragma_update(None, "key", passphrase)
+}
+
+/// True when the file at `path` exists but can't be read without a key.
+pub fn is_encrypted(path: &str) -> bool {
+    if !Path::new(path).exists() {
+        return false;
+    }
+    let Ok(conn) = Connection::open(path) else { return false; };
+    matches!(
+        conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |row| row.get::<_, i64>(0)),
+        Err(Error::SqliteFailure(e, _)) if e.code == ErrorCode::NotADatabase
+    )
+}
+
+/// Copy every table, index and row from `conn` into a new database at
+/// `dest`, encrypted with `passphrase` (or in plaintext when `None`).
+pub fn export(conn: &Connection, dest: &str, passphrase: Option<&str>) -> Result<()> {
+    let _ = std::fs::remove_file(dest);
+    conn.execute(
+        "ATTACH DATABASE ?1 AS export KEY ?2",
+        (dest, passphrase.unwrap_or("")),
+    )?;
+    let exported = conn.query_row("SELECT sqlcipher_export('export')", [], |_| Ok(()));
+    conn.execute("DETACH DATABASE export", [])?;
+    exported
+}
+
+/// Check that the database at `path` can be read with `passphrase`.
+pub fn verify(path: &str, passphrase: Option<&str>) -> Result<()> {
+    let conn = Connection::open(path)?;
+    if let Some(passphrase) = passphrase {
+        apply_key(&conn, passphrase)?;
+    }
+    conn.query_row("SELECT COUNT(*) FROM sqlite_master", [], |_| Ok(()))
+}
+
+/// Move the database file at `src` over `dest`, dropping the old WAL and
+/// shared-memory files. All connections to both must be closed first.
+pub fn replace_file(src: &str, dest: &str) -> Result<()> {
+    for suffix in ["-wal", "-shm"] {
+        let _ = std::fs::remove_file(format!("{dest}{suffix}"));
+    }
+    std::fs::rename(src, dest).map_err(|e| {
+        Error::SqliteFailure(
+            rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CANTOPEN),
+            Some(format!("could not replace {dest}: {e}")),
+        )
+    })
+}
@@ -0,0 +1,89 @@
+// database/dependencies.rs
+//
+// "Blocked by" links between tasks (`task_dependencies`). A task is blocked
+// while any task it depends on isn't done; the flag itself is computed in
+// `TASK_COLUMNS`, so every task query reports it. Links that would close a
+// cycle are refused, since a cycle could never become actionable.
+
+use rusqlite::{Connection, Result};
+
+use super::models::{DependencyGraph, TaskDependency};
+use super::tasks::{map_task_rows, TASK_COLUMNS};
+
+/// Record that `task_id` is blocked by `blocked_by`. Returns `Ok(false)`
+/// without changing anything if the link would create a cycle (including a
+/// task blocking itself). A missing task fails with `QueryReturnedNoRows`.
+pub fn add(conn: &Connection, task_id: i64, blocked_by: i64) -> Result<bool> {
+    let tx = conn.unchecked_transaction()?;
+    for id in [task_id, blocked_by] {
+        tx.query_row("SELECT 1 FROM tasks WHERE id = ?1", [id], |_| Ok(()))?;
+    }
+
+    // Would `blocked_by` (transitively) wait on `task_id`?
+    let cycle: bool = tx.query_row(
+        "WITH RECURSIVE chain(id) AS (
+             SELECT ?1
+             UNION
+             SELECT d.blocked_by FROM task_dependencies d JOIN chain c ON d.task_id = c.id
+         )
+         SELECT EXISTS (SELECT 1 FROM chain WHERE id = ?2)",
+        (blocked_by, task_id),
+        |row| row.get(0),
+    )?;
+    if cycle {
+        return Ok(false);
+    }
+
+    tx.execute(
+        "INSERT OR IGNORE INTO task_dependencies (task_id, blocked_by) VALUES (?1, ?2)",
+        (task_id, blocked_by),
+    )?;
+    tx.commit()?;
+    Ok(true)
+}
+
+pub fn remove(conn: &Connection, task_id: i64, blocked_by: i64) -> Result<()> {
+    conn.execute(
+        "DELETE FROM task_dependencies WHERE task_id = ?1 AND blocked_by = ?2",
+        (task_id, blocked_by),
+    )?;
+    Ok(())
+}
+
+/// Every link touching a task in `project_id` (or every link when `None`),
+/// plus the tasks on both ends. Tasks of the project without links are
+/// included too, so the graph shows the whole project.
+pub fn graph(conn: &Connection, project_id: Option<i64>) -> Result<DependencyGraph> {
+    let mut stmt = conn.prepare(
+        "SELECT d.task_id, d.blocked_by
+         FROM task_dependencies d
+         JOIN tasks a ON a.id = d.task_id
+         JOIN tasks b ON b.id = d.blocked_by
+         WHERE ?1 IS NULL OR a.project_id = ?1 OR b.project_id = ?1
+         ORDER BY d.task_id, d.blocked_by",
+    )?;
+    let edges: Vec<TaskDependency> = stmt
+        .query_map([project_id], |row| {
+            Ok(TaskDependency { task_id: row.get(0)?, blocked_by: row.get(1)? })
+        })?
+        .filter_map(Result::ok)
+        .collect();
+
+    let sql = format!(
+        "SELECT {TASK_COLUMNS} FROM tasks t
+         WHERE (?1 IS NOT NULL AND t.project_id = ?1)
+            OR t.id IN (SELECT d.task_id FROM task_dependencies d
+                        JOIN tasks a ON a.id = d.task_id
+                        JOIN tasks b ON b.id = d.blocked_by
+                        WHERE ?1 IS NULL OR a.project_id = ?1 OR b.project_id = ?1
+                        UNION
+                        SELECT d.blocked_by FROM task_dependencies d
+                        JOIN tasks a ON a.id = d.task_id
+                        JOIN tasks b ON b.id = d.blocked_by
+                        WHERE ?1 IS NULL OR a.project_id = ?1 OR b.project_id = ?1)
+         ORDER BY t.position, t.id DESC"
+    );
+    let tasks = map_task_rows(conn, &sql, [project_id])?;
+
+    Ok(DependencyGraph { tasks, edges })
+}
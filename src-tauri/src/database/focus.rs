@@ -0,0 +1,241 @@
+// database/focus.rs
+//
+// Focus quality from the timer's action log. A work session runs from its
+// "start" row to the next "start" of any phase; "pause" / "resume" rows
+// split it into uninterrupted stretches, and it ends with "complete"
+// (followed by "reset" or "switch" when it was cut short, see `switches`).
+//
+// The focus score (0–100) weighs three ratios:
+//   completion — completed sessions out of those that ended
+//   continuity — focus time out of focus plus pause time
+//   stretch    — average stretch against a full 25-minute pomodoro
+// A day's score uses the same ratios over all its sessions.
+
+use std::collections::BTreeMap;
+
+use rusqlite::{Connection, Result};
+
+use super::calendar::Calendar;
+use super::models::{FocusDay, FocusReport, FocusSession, SessionOutcome};
+
+const COMPLETION_WEIGHT: f64 = 0.4;
+const CONTINUITY_WEIGHT: f64 = 0.3;
+const STRETCH_WEIGHT:    f64 = 0.3;
+
+/// A stretch this long counts as fully focused.
+const FULL_STRETCH_SECONDS: f64 = 25.0 * 60.0;
+
+/// Work sessions started in `[start, end]`, and per-day totals of them.
+pub fn report(conn: &Connection, calendar: &Calendar, start: i64, end: i64) -> Result<FocusReport> {
+    let sessions = sessions(conn, start, end)?;
+
+    let mut days: BTreeMap<String, Totals> = BTreeMap::new();
+    for (session, totals) in &sessions {
+        let day = calendar.day(session.started_at).format("%Y-%m-%d").to_string();
+        days.entry(day).or_default().add(totals);
+    }
+
+    Ok(FocusReport {
+        days: days
+            .into_iter()
+            .map(|(day, t)| FocusDay {
+                day,
+                sessions:            t.sessions,
+                completed:           t.completed,
+                reset:               t.reset,
+                switched:            t.switched,
+                focus_seconds:       t.focus_seconds,
+                pauses:              t.pauses,
+                pause_seconds:       t.pause_seconds,
+                avg_stretch_seconds: t.avg_stretch(),
+                focus_score:         t.score(),
+            })
+            .collect(),
+        sessions: sessions.into_iter().map(|(session, _)| session).collect(),
+    })
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+/// Running sums over one or more sessions.
+#[derive(Default)]
+struct Totals {
+    sessions:        i64,
+    completed:       i64,
+    reset:           i64,
+    switched:        i64,
+    focus_seconds:   i64,
+    pauses:          i64,
+    pause_seconds:   i64,
+    stretches:       i64,
+    stretch_seconds: i64,
+}
+
+impl Totals {
+    fn add(&mut self, other: &Totals) {
+        self.sessions        += other.sessions;
+        self.completed       += other.completed;
+        self.reset           += other.reset;
+        self.switched        += other.switched;
+        self.focus_seconds   += other.focus_seconds;
+        self.pauses          += other.pauses;
+        self.pause_seconds   += other.pause_seconds;
+        self.stretches       += other.stretches;
+        self.stretch_seconds += other.stretch_seconds;
+    }
+
+    fn avg_stretch(&self) -> i64 {
+        if self.stretches == 0 { 0 } else { self.stretch_seconds / self.stretches }
+    }
+
+    fn score(&self) -> u32 {
+        let ended = self.completed + self.reset + self.switched;
+        let completion = if ended == 0 { 1.0 } else { self.completed as f64 / ended as f64 };
+        let active = self.focus_seconds + self.pause_seconds;
+        let continuity = if active == 0 { 0.0 } else { self.focus_seconds as f64 / active as f64 };
+        let stretch = (self.avg_stretch() as f64 / FULL_STRETCH_SECONDS).min(1.0);
+        let score = COMPLETION_WEIGHT * completion + CONTINUITY_WEIGHT * continuity + STRETCH_WEIGHT * stretch;
+        (score * 100.0).round() as u32
+    }
+}
+
+/// A session being rebuilt from the log.
+struct Open {
+    task_name:    String,
+    started_at:   i64,
+    last_at:      i64,
+    /// When the current stretch began; `None` while paused
+    active_since: Option<i64>,
+    paused_at:    Option<i64>,
+    totals:       Totals,
+    outcome:      SessionOutcome,
+}
+
+impl Open {
+    fn new(task_name: String, at: i64) -> Self {
+        Open {
+            task_name,
+            started_at:   at,
+            last_at:      at,
+            active_since: Some(at),
+            paused_at:    None,
+            totals:       Totals { sessions: 1, ..Totals::default() },
+            outcome:      SessionOutcome::Unfinished,
+        }
+    }
+
+    /// Apply one log row. Only "reset" and "switch" still count once the
+    /// session has ended, since they are logged right after "complete".
+    fn apply(&mut self, action: &str, elapsed: i64, at: i64) {
+        if self.outcome != SessionOutcome::Unfinished && action != "reset" && action != "switch" {
+            return;
+        }
+        self.last_at = at;
+        match action {
+            "pause" => {
+                if let Some(since) = self.active_since.take() {
+                    self.totals.pauses += 1;
+                    self.end_stretch(since, at);
+                    self.paused_at = Some(at);
+                }
+            }
+            "resume" => {
+                if let Some(paused_at) = self.paused_at.take() {
+                    self.totals.pause_seconds += at - paused_at;
+                    self.active_since = Some(at);
+                }
+            }
+            "complete" => {
+                self.totals.focus_seconds += elapsed;
+                self.outcome = SessionOutcome::Completed;
+                self.stop(at);
+            }
+            "reset" => {
+                self.outcome = SessionOutcome::Reset;
+                self.stop(at);
+            }
+            "switch" => {
+                self.outcome = SessionOutcome::Switched;
+                self.stop(at);
+            }
+            _ => {}
+        }
+    }
+
+    fn stop(&mut self, at: i64) {
+        if let Some(since) = self.active_since.take() {
+            self.end_stretch(since, at);
+        }
+        if let Some(paused_at) = self.paused_at.take() {
+            self.totals.pause_seconds += at - paused_at;
+        }
+    }
+
+    fn end_stretch(&mut self, since: i64, at: i64) {
+        self.totals.stretches += 1;
+        self.totals.stretch_seconds += at - since;
+    }
+
+    fn finish(mut self) -> (FocusSession, Totals) {
+        let end = self.last_at;
+        self.stop(end);
+        match self.outcome {
+            SessionOutcome::Completed  => self.totals.completed += 1,
+            SessionOutcome::Reset      => self.totals.reset += 1,
+            SessionOutcome::Switched   => self.totals.switched += 1,
+            SessionOutcome::Unfinished => {}
+        }
+        let session = FocusSession {
+            task_name:           self.task_name,
+            started_at:          self.started_at,
+            ended_at:            end,
+            outcome:             self.outcome,
+            focus_seconds:       self.totals.focus_seconds,
+            pauses:              self.totals.pauses,
+            pause_seconds:       self.totals.pause_seconds,
+            avg_stretch_seconds: self.totals.avg_stretch(),
+            focus_score:         self.totals.score(),
+        };
+        (session, self.totals)
+    }
+}
+
+/// Rebuild the work sessions started in `[start, end]` from the log. Rows
+/// of breaks are ignored.
+fn sessions(conn: &Connection, start: i64, end: i64) -> Result<Vec<(FocusSession, Totals)>> {
+    let mut stmt = conn.prepare(
+        "SELECT action, task_name, elapsed, phase, timestamp
+         FROM   pomodoro_sessions
+         WHERE  timestamp >= ?1
+         ORDER  BY id",
+    )?;
+    let rows = stmt.query_map([start], |row| {
+        Ok((
+            row.get::<_, String>(0)?,
+            row.get::<_, String>(1)?,
+            row.get::<_, i64>(2)?,
+            row.get::<_, i64>(3)?,
+            row.get::<_, i64>(4)?,
+        ))
+    })?;
+
+    let mut sessions = Vec::new();
+    let mut open: Option<Open> = None;
+    for (action, task_name, elapsed, phase, at) in rows.filter_map(Result::ok) {
+        if action == "start" {
+            sessions.extend(open.take().map(Open::finish));
+            if at > end {
+                break;
+            }
+            if phase == 0 {
+                open = Some(Open::new(task_name, at));
+            }
+            continue;
+        }
+        if let Some(session) = open.as_mut() {
+            session.apply(&action, elapsed, at);
+        }
+    }
+    sessions.extend(open.take().map(Open::finish));
+    Ok(sessions)
+}
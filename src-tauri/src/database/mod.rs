@@ -5,177 +5,683 @@
 // Each sub-module owns one concern:
 //   models   — shared data structs (no logic)
 //   sessions — pomodoro session log
+//   stats    — grouped session totals, weekly/monthly trends and the focus heatmap
+//   focus    — focus quality metrics rebuilt from the timer's action log
+//   switches — task-switch log and context-switching statistics
 //   tasks    — task CRUD + tag linking
-//   projects — project CRUD
-//   tags     — tag CRUD
-//   settings — key-value settings store
+//   task_query — structured task filtering, sorting and pagination
+//   task_batch — one operation over many tasks in a single transaction
+//   task_tree — subtask hierarchies, re-parenting and roll-ups
+//   recurrence — recurrence rules and spawning the next occurrence
+//   dependencies — blocked-by links between tasks
+//   templates — reusable task templates and instantiating them
+//   reminders — due-date reminders and the log of those already sent
+//   workflows — per-project status columns, done statuses, WIP limits and transitions
+//   projects — project CRUD, metadata, nesting and archiving
+//   budgets  — project time budgets and threshold alerts
+//   tags     — tag CRUD, case-insensitive names and merging
+//   settings — key-value settings store and the typed Settings model
+//   secrets  — encrypted credential rows (ciphertext only)
+//   calendar — timezone, day rollover and week start for date bucketing
+//   pool     — connection setup (WAL) and the read-only connection pool
+//   cipher   — SQLCipher key handling and encrypted export
 //
 // External code imports from `crate::database::*` and interacts only
 // with the `Database` struct; sub-module internals stay private.
 
+mod budgets;
+mod calendar;
+mod cipher;
+mod dependencies;
+mod focus;
 mod models;
+mod pool;
 mod programs;
 mod projects;
+mod recurrence;
+mod reminders;
+mod secrets;
 mod sessions;
 mod settings;
+mod stats;
+mod switches;
 mod tags;
+mod task_batch;
+mod task_query;
+mod task_tree;
 mod tasks;
+mod templates;
+mod workflows;
 
 // Re-export models so the rest of the crate can use them without
 // reaching into sub-modules.
-pub use models::{DailyStats, PomodoroRecord, Project, Tag, Task, TaskStats};
+pub use models::{
+    BatchOperation, BatchResult, BillableSession, BudgetAlert, BudgetPeriod, BudgetStatus,
+    DailyStats, DependencyGraph, FocusDay, FocusHeatmap, FocusReport, FocusSession, GroupStats,
+    PeriodTrend, PomodoroRecord, Project, ProjectUpdate, Recurrence, Reminder, ReminderKind,
+    SeriesBy, SeriesLine, SessionOutcome, Settings, StatsGroup, StatusChange, SwitchDay,
+    SwitchPair, SwitchReport, Tag,
+    Task, TaskCompletion, TaskDependency, TaskNode, TaskPage, TaskQuery, TaskRollup, TaskStats,
+    TaskTemplate, TemplateSubtask, TrendPeriod, TrendSeries, Workflow, WorkflowStatus,
+    WorkflowTransition,
+};
+pub use calendar::Calendar;
+pub use secrets::SecretRow;
 
 // Re-export TrackedProgram so commands/programs.rs can define it once
 // and the DB layer can use it without a circular dep.
 pub use crate::commands::programs::TrackedProgram;
 
+use std::collections::HashMap;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
 use rusqlite::{Connection, Result};
 
 // ── Database façade ───────────────────────────────────────────────────────
 
-/// Holds the open SQLite connection. All methods delegate to the appropriate
-/// sub-module, keeping this struct lean and easy to test.
+/// Holds the dedicated writer connection and the read pool. All methods
+/// delegate to the appropriate sub-module, keeping this struct lean and easy
+/// to test. `Database` is `Sync`, so it is shared as a plain `Arc<Database>`;
+/// callers on the async runtime should go through `commands::with_db`.
 pub struct Database {
-    conn: Connection,
+    path:       String,
+    passphrase: Mutex<Option<String>>,
+    /// `None` while closed; see `set_passphrase`.
+    writer:     Mutex<Option<Connection>>,
+    readers:    pool::ReadPool,
+    calendar:   calendar::SharedCalendar,
 }
 
 impl Database {
     /// Open (or create) the database at `path` and run schema migrations.
     pub fn new(path: &str) -> Result<Self> {
-        let conn = Connection::open(path)?;
-        schema::migrate(&conn)?;
-        Ok(Database { conn })
+        Self::open(path, None)
+    }
+
+    /// Open the database at `path`, unlocking it with `passphrase` when it is
+    /// encrypted. A wrong passphrase fails with `ErrorCode::NotADatabase`.
+    pub fn open(path: &str, passphrase: Option<&str>) -> Result<Self> {
+        let calendar = calendar::SharedCalendar::default();
+        let writer = pool::open(path, passphrase, &calendar)?;
+        schema::migrate(&writer)?;
+        calendar::set(&calendar, Calendar::from_settings(&settings::load(&writer)?));
+        let readers = pool::ReadPool::open(path, pool::READERS, passphrase, &calendar)?;
+        Ok(Database {
+            path:       path.to_string(),
+            passphrase: Mutex::new(passphrase.map(String::from)),
+            writer:     Mutex::new(Some(writer)),
+            readers,
+            calendar,
+        })
+    }
+
+    /// Run `f` on one of the read-only connections.
+    fn read<T>(&self, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        self.readers.with(f)
+    }
+
+    /// Run `f` on the single writer connection.
+    fn write<T>(&self, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        let writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        f(writer.as_ref().ok_or_else(pool::closed)?)
+    }
+
+    // ── Encryption ────────────────────────────────────────────────────────
+
+    /// True when the file at `path` is encrypted and needs a passphrase
+    /// before it can be opened.
+    pub fn is_encrypted_file(path: &str) -> bool {
+        cipher::is_encrypted(path)
+    }
+
+    pub fn is_encrypted(&self) -> bool {
+        self.passphrase.lock().unwrap_or_else(PoisonError::into_inner).is_some()
+    }
+
+    /// Compare `candidate` against the passphrase the database was unlocked with.
+    pub fn check_passphrase(&self, candidate: &str) -> bool {
+        self.passphrase
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .as_deref()
+            == Some(candidate)
+    }
+
+    /// Re-encrypt the database file in place with `passphrase`, or decrypt it
+    /// when `None`. Covers enabling, changing and removing encryption.
+    /// Every connection is closed around the file swap. The original file is
+    /// kept until the new one has been opened; on any error it is put back
+    /// and reopened with the old passphrase. If that fails too, the database
+    /// stays closed, failing every call, until `reopen` succeeds.
+    pub fn set_passphrase(&self, passphrase: Option<&str>) -> Result<()> {
+        let old = self.passphrase.lock().unwrap_or_else(PoisonError::into_inner).clone();
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        let Some(conn) = writer.as_ref() else { return Err(pool::closed()) };
+        let tmp = format!("{}.rekey", self.path);
+        let backup = format!("{}.bak", self.path);
+        if let Err(e) = cipher::export(conn, &tmp, passphrase)
+            .and_then(|()| cipher::verify(&tmp, passphrase))
+        {
+            let _ = std::fs::remove_file(&tmp);
+            return Err(e);
+        }
+
+        // Readers are locked after the writer; they never take the writer
+        // lock themselves, so this ordering can't deadlock.
+        let mut readers = self.readers.lock_all();
+        writer.take();
+        for reader in readers.iter_mut() {
+            reader.take();
+        }
+
+        let set_aside = cipher::replace_file(&self.path, &backup);
+        let moved = set_aside.is_ok();
+        let swapped = set_aside
+            .and_then(|()| cipher::replace_file(&tmp, &self.path))
+            .and_then(|()| self.open_connections(passphrase, readers.len()));
+        let outcome = match swapped {
+            Ok(conns) => {
+                let _ = std::fs::remove_file(&backup);
+                *self.passphrase.lock().unwrap_or_else(PoisonError::into_inner) =
+                    passphrase.map(String::from);
+                Ok(conns)
+            }
+            Err(e) => {
+                let _ = std::fs::remove_file(&tmp);
+                // Only reopen the original: a missing file would be
+                // recreated empty, and the new one has another key.
+                let restored = !moved || cipher::replace_file(&backup, &self.path).is_ok();
+                if restored {
+                    if let Ok(conns) = self.open_connections(old.as_deref(), readers.len()) {
+                        install(&mut writer, &mut readers, conns);
+                    }
+                }
+                Err(e)
+            }
+        };
+        outcome.map(|conns| install(&mut writer, &mut readers, conns))
+    }
+
+    /// True while the database is closed after a failed passphrase change.
+    pub fn is_closed(&self) -> bool {
+        self.writer.lock().unwrap_or_else(PoisonError::into_inner).is_none()
+    }
+
+    /// Reopen a closed database with `passphrase`, first putting back the
+    /// original file if a failed passphrase change left it aside. The
+    /// passphrase is ignored when the file isn't encrypted. A no-op if the
+    /// database is open.
+    pub fn reopen(&self, passphrase: &str) -> Result<()> {
+        let mut writer = self.writer.lock().unwrap_or_else(PoisonError::into_inner);
+        if writer.is_some() {
+            return Ok(());
+        }
+        let mut readers = self.readers.lock_all();
+        let backup = format!("{}.bak", self.path);
+        if std::path::Path::new(&backup).exists() {
+            cipher::replace_file(&backup, &self.path)?;
+        }
+        if !std::path::Path::new(&self.path).exists() {
+            return Err(pool::closed());
+        }
+        let passphrase = cipher::is_encrypted(&self.path).then_some(passphrase);
+        let conns = self.open_connections(passphrase, readers.len())?;
+        install(&mut writer, &mut readers, conns);
+        *self.passphrase.lock().unwrap_or_else(PoisonError::into_inner) = passphrase.map(String::from);
+        Ok(())
+    }
+
+    /// Open a writer and `readers` read-only connections on the database file.
+    fn open_connections(&self, passphrase: Option<&str>, readers: usize) -> Result<(Connection, Vec<Connection>)> {
+        let writer = pool::open(&self.path, passphrase, &self.calendar)?;
+        let readers = (0..readers)
+            .map(|_| pool::open_reader(&self.path, passphrase, &self.calendar))
+            .collect::<Result<Vec<_>>>()?;
+        Ok((writer, readers))
     }
 
     // ── Session log ───────────────────────────────────────────────────────
 
     pub fn log_action(&self, task_name: &str, action: &str, elapsed: u64, phase: u8) -> Result<()> {
-        sessions::log_action(&self.conn, task_name, action, elapsed, phase)
+        self.write(|c| sessions::log_action(c, task_name, action, elapsed, phase))
+    }
+
+    /// Log the start of a work session, recording a switch when the last
+    /// work session was on another task.
+    pub fn log_work_start(&self, task_name: &str, interrupted: bool) -> Result<()> {
+        self.write(|c| switches::log_work_start(c, task_name, interrupted))
     }
 
     pub fn log_session_complete(&self, task_name: &str, elapsed_seconds: u64, phase: u8) -> Result<()> {
-        sessions::log_session_complete(&self.conn, task_name, elapsed_seconds, phase)
+        self.write(|c| sessions::log_session_complete(c, task_name, elapsed_seconds, phase))
     }
 
     pub fn get_recent(&self, limit: i64) -> Result<Vec<PomodoroRecord>> {
-        sessions::get_recent(&self.conn, limit)
+        self.read(|c| sessions::get_recent(c, limit))
     }
 
     pub fn get_all(&self) -> Result<Vec<PomodoroRecord>> {
-        sessions::get_all(&self.conn)
+        self.read(sessions::get_all)
     }
 
     pub fn insert_record(&self, task_name: &str, action: &str, elapsed: u64, phase: u8, timestamp: i64) -> Result<()> {
-        sessions::insert_record(&self.conn, task_name, action, elapsed, phase, timestamp)
+        self.write(|c| sessions::insert_record(c, task_name, action, elapsed, phase, timestamp))
     }
 
     pub fn clear_all(&self) -> Result<()> {
-        sessions::clear_all(&self.conn)
+        self.write(sessions::clear_all)
     }
 
     pub fn get_unique_task_names(&self, limit: i64) -> Result<Vec<String>> {
-        sessions::get_unique_task_names(&self.conn, limit)
+        self.read(|c| sessions::get_unique_task_names(c, limit))
     }
 
     pub fn get_task_stats(&self, start: i64, end: i64) -> Result<Vec<TaskStats>> {
-        sessions::get_task_stats(&self.conn, start, end)
+        self.read(|c| sessions::get_task_stats(c, start, end))
     }
 
     pub fn get_daily_breakdown(&self, start: i64, end: i64) -> Result<Vec<DailyStats>> {
-        sessions::get_daily_breakdown(&self.conn, start, end)
+        self.read(|c| sessions::get_daily_breakdown(c, start, end))
+    }
+
+    /// Work time in `[start, end]` by project, tag, source or status.
+    pub fn get_grouped_stats(&self, group: StatsGroup, start: i64, end: i64) -> Result<Vec<GroupStats>> {
+        self.read(|c| stats::grouped(c, group, start, end))
+    }
+
+    /// Weekly or monthly work totals over `[start, end]` with comparisons.
+    pub fn get_trends(&self, period: TrendPeriod, start: i64, end: i64) -> Result<Vec<PeriodTrend>> {
+        self.read(|c| stats::trends(c, &self.calendar(), period, start, end))
+    }
+
+    /// Work time in `[start, end]` by weekday and local hour.
+    pub fn get_focus_heatmap(&self, start: i64, end: i64) -> Result<FocusHeatmap> {
+        self.read(|c| stats::heatmap(c, &self.calendar(), start, end))
+    }
+
+    /// Task switches in `[start, end]`: per day, time before switching and top pairs.
+    pub fn get_switch_stats(&self, start: i64, end: i64) -> Result<SwitchReport> {
+        self.read(|c| switches::report(c, start, end))
+    }
+
+    /// Per-session and per-day focus metrics for work sessions started in `[start, end]`.
+    pub fn get_focus_metrics(&self, start: i64, end: i64) -> Result<FocusReport> {
+        self.read(|c| focus::report(c, &self.calendar(), start, end))
+    }
+
+    pub fn get_trend_series(
+        &self,
+        period: TrendPeriod,
+        by:     SeriesBy,
+        start:  i64,
+        end:    i64,
+        limit:  usize,
+    ) -> Result<TrendSeries> {
+        self.read(|c| stats::series(c, &self.calendar(), period, by, start, end, limit))
+    }
+
+    /// Completed work sessions in `[start, end]` with their task's project.
+    pub fn get_billable_sessions(&self, start: i64, end: i64) -> Result<Vec<BillableSession>> {
+        self.read(|c| sessions::get_billable(c, start, end))
     }
 
     // ── Tasks ─────────────────────────────────────────────────────────────
 
     pub fn create_task(&self, task: Task) -> Result<i64> {
-        tasks::create(&self.conn, task)
+        self.write(|c| tasks::create(c, task))
+    }
+
+    /// Create a task if its status is allowed by the project's workflow;
+    /// otherwise return the rejected change.
+    pub fn create_task_checked(&self, task: Task) -> Result<std::result::Result<i64, StatusChange>> {
+        self.write(|c| tasks::create_checked(c, task))
     }
 
     pub fn save_external_task(&self, task: Task) -> Result<i64> {
-        tasks::save_external(&self.conn, task)
+        self.write(|c| tasks::save_external(c, task))
     }
 
     /// Returns true if a task with the given `external_id` and `source` already
     /// exists in the database (i.e. was previously imported).
     pub fn is_external_task_imported(&self, external_id: &str, source: &str) -> Result<bool> {
-        tasks::is_imported(&self.conn, external_id, source)
+        self.read(|c| tasks::is_imported(c, external_id, source))
     }
 
-    pub fn update_task(&self, task: Task) -> Result<()> {
-        tasks::update(&self.conn, task)
+    /// Nothing is saved unless the status change is `Allowed` by the task's
+    /// workflow. Saving a recurring task as done also creates its next occurrence.
+    pub fn update_task(&self, task: Task) -> Result<StatusChange> {
+        self.write(|c| tasks::update(c, &self.calendar(), task))
     }
 
     pub fn delete_task(&self, id: i64) -> Result<()> {
-        tasks::delete(&self.conn, id)
+        self.write(|c| tasks::delete(c, id))
     }
 
-    pub fn get_tasks(&self, filter_project: Option<i64>, filter_tag: Option<i64>, filter_status: Option<String>) -> Result<Vec<Task>> {
-        tasks::list(&self.conn, filter_project, filter_tag, filter_status)
+    pub fn get_tasks(&self, filter_project: Option<i64>, filter_tag: Option<i64>, filter_status: Option<String>, actionable: bool) -> Result<Vec<Task>> {
+        self.read(|c| tasks::list(c, filter_project, filter_tag, filter_status, actionable))
+    }
+
+    /// Run a structured `TaskQuery` and return one page of results, or why
+    /// its cursor was rejected.
+    pub fn query_tasks(&self, query: &TaskQuery) -> Result<std::result::Result<TaskPage, String>> {
+        self.read(|c| task_query::query(c, query))
     }
 
     pub fn search_tasks(&self, query: &str) -> Result<Vec<Task>> {
-        tasks::search(&self.conn, query)
+        self.read(|c| tasks::search(c, query))
+    }
+
+    /// Reject recurrence rules with out-of-range parameters.
+    pub fn validate_recurrence(rule: &Recurrence) -> std::result::Result<(), String> {
+        recurrence::validate(rule)
+    }
+
+    pub fn reorder_tasks(&self, ids: &[i64]) -> Result<()> {
+        self.write(|c| tasks::reorder(c, ids))
+    }
+
+    pub fn move_task_to_column(&self, id: i64, status: &str, column_ids: &[i64]) -> Result<StatusChange> {
+        self.write(|c| tasks::move_to_column(c, &self.calendar(), id, status, column_ids))
+    }
+
+    /// Apply one operation to many tasks in one transaction; one result per id.
+    pub fn batch_update_tasks(&self, ids: &[i64], op: &BatchOperation) -> Result<Vec<BatchResult>> {
+        self.write(|c| task_batch::apply(c, &self.calendar(), ids, op))
+    }
+
+    // ── Workflows ─────────────────────────────────────────────────────────
+
+    /// The workflow of `project_id`, or the default one.
+    pub fn get_workflow(&self, project_id: Option<i64>) -> Result<Workflow> {
+        self.read(|c| workflows::for_project(c, project_id))
+    }
+
+    /// Returns the statuses still in use that `workflow` lacks; when there
+    /// are any, nothing changes.
+    pub fn set_workflow(&self, project_id: i64, workflow: Option<&Workflow>) -> Result<Vec<String>> {
+        self.write(|c| workflows::set(c, project_id, workflow))
+    }
+
+    /// The status an imported task with `external` status gets in `project_id`.
+    pub fn import_status(&self, project_id: Option<i64>, external: &str) -> Result<String> {
+        self.read(|c| Ok(workflows::for_project(c, project_id)?.import_status(external).to_string()))
+    }
+
+    /// Trim and check a workflow before it is saved.
+    pub fn validate_workflow(workflow: &mut Workflow) -> std::result::Result<(), String> {
+        workflows::validate(workflow)
+    }
+
+    // ── Dependencies ──────────────────────────────────────────────────────
+
+    /// Returns false if the link would create a dependency cycle.
+    pub fn add_task_dependency(&self, task_id: i64, blocked_by: i64) -> Result<bool> {
+        self.write(|c| dependencies::add(c, task_id, blocked_by))
+    }
+
+    pub fn remove_task_dependency(&self, task_id: i64, blocked_by: i64) -> Result<()> {
+        self.write(|c| dependencies::remove(c, task_id, blocked_by))
+    }
+
+    pub fn get_dependency_graph(&self, project_id: Option<i64>) -> Result<DependencyGraph> {
+        self.read(|c| dependencies::graph(c, project_id))
+    }
+
+    // ── Templates ─────────────────────────────────────────────────────────
+
+    pub fn create_template(&self, template: &TaskTemplate) -> Result<i64> {
+        self.write(|c| templates::create(c, template))
+    }
+
+    pub fn get_templates(&self) -> Result<Vec<TaskTemplate>> {
+        self.read(templates::list)
+    }
+
+    pub fn get_template(&self, id: i64) -> Result<Option<TaskTemplate>> {
+        self.read(|c| templates::get(c, id))
+    }
+
+    pub fn update_template(&self, template: &TaskTemplate) -> Result<()> {
+        self.write(|c| templates::update(c, template))
+    }
+
+    pub fn delete_template(&self, id: i64) -> Result<()> {
+        self.write(|c| templates::delete(c, id))
+    }
+
+    /// Create a task tree from a template; returns the root task's id, or
+    /// the rejected change if the workflow's initial column has no room.
+    pub fn instantiate_template(
+        &self,
+        id:       i64,
+        values:   &HashMap<String, String>,
+        due_date: Option<i64>,
+    ) -> Result<std::result::Result<i64, StatusChange>> {
+        self.write(|c| templates::instantiate(c, &self.calendar(), id, values, due_date))
+    }
+
+    /// Placeholders in a template that the caller has to supply.
+    pub fn template_placeholders(template: &TaskTemplate) -> Vec<String> {
+        templates::placeholders(template)
+    }
+
+    /// Trim and check a template before it is saved.
+    pub fn validate_template(template: &mut TaskTemplate) -> std::result::Result<(), String> {
+        templates::validate(template)
+    }
+
+    // ── Reminders ─────────────────────────────────────────────────────────
+
+    /// Record and return due-date reminders that are now due.
+    pub fn take_due_reminders(&self, offsets: &[u32]) -> Result<Vec<Reminder>> {
+        self.write(|c| reminders::take_due(c, offsets))
+    }
+
+    // ── Subtask trees ─────────────────────────────────────────────────────
+
+    pub fn get_task_tree(&self, root: Option<i64>) -> Result<Vec<TaskNode>> {
+        self.read(|c| task_tree::load(c, root))
+    }
+
+    /// Returns false if the move would put a task under its own subtree.
+    pub fn move_task(&self, id: i64, new_parent: Option<i64>) -> Result<bool> {
+        self.write(|c| task_tree::reparent(c, id, new_parent))
+    }
+
+    pub fn complete_task(&self, id: i64, include_subtasks: bool) -> Result<TaskCompletion> {
+        self.write(|c| task_tree::complete(c, &self.calendar(), id, include_subtasks))
     }
 
     // ── Projects ──────────────────────────────────────────────────────────
 
+    /// Returns the existing project's id if the name (ignoring case) is taken.
     pub fn create_project(&self, name: &str, color: Option<&str>) -> Result<i64> {
-        projects::create(&self.conn, name, color)
+        self.write(|c| projects::create(c, name, color))
     }
 
-    /// Find an existing project by name or create a new one. Returns the project id.
+    /// Find an existing project by name (ignoring case) or create a new one.
+    /// Returns the project id.
     pub fn find_or_create_project(&self, name: &str) -> Result<i64> {
-        projects::find_or_create(&self.conn, name)
+        self.write(|c| projects::find_or_create(c, name))
     }
 
-    pub fn get_projects(&self) -> Result<Vec<Project>> {
-        projects::list(&self.conn)
+    /// Projects ordered by name. Archived ones are only included on request.
+    pub fn get_projects(&self, include_archived: bool) -> Result<Vec<Project>> {
+        self.read(|c| projects::list(c, include_archived))
+    }
+
+    pub fn get_project(&self, id: i64) -> Result<Option<Project>> {
+        self.read(|c| projects::get(c, id))
+    }
+
+    pub fn update_project(&self, project: &Project) -> Result<ProjectUpdate> {
+        self.write(|c| projects::update(c, project))
+    }
+
+    /// Returns false if another project already has the name.
+    pub fn rename_project(&self, id: i64, name: &str) -> Result<bool> {
+        self.write(|c| projects::rename(c, id, name))
+    }
+
+    /// Move all tasks and sub-projects of `source_ids` into `target_id` and
+    /// delete the sources. Returns the number of tasks moved, or `None` if
+    /// the target is nested under a source.
+    pub fn merge_projects(&self, source_ids: &[i64], target_id: i64) -> Result<Option<usize>> {
+        self.write(|c| projects::merge(c, source_ids, target_id))
+    }
+
+    /// Archive or restore a project and all of its sub-projects.
+    pub fn set_project_archived(&self, id: i64, archived: bool) -> Result<()> {
+        self.write(|c| projects::set_archived(c, id, archived))
+    }
+
+    /// Budget usage of every active budgeted project, or of one project.
+    pub fn get_budget_status(&self, project_id: Option<i64>) -> Result<Vec<BudgetStatus>> {
+        self.read(|c| budgets::status(c, &self.calendar(), project_id))
+    }
+
+    /// Record thresholds newly crossed after a session on `task_name`
+    /// and return the ones to notify about.
+    pub fn take_budget_alerts(&self, task_name: &str, thresholds: &[u32]) -> Result<Vec<BudgetAlert>> {
+        self.write(|c| budgets::take_alerts(c, &self.calendar(), task_name, thresholds))
+    }
+
+    /// Normalise and check user-editable project fields.
+    pub fn validate_project(project: &mut Project) -> std::result::Result<(), String> {
+        projects::validate(project)
     }
 
     pub fn delete_project(&self, id: i64) -> Result<()> {
-        projects::delete(&self.conn, id)
+        self.write(|c| projects::delete(c, id))
     }
 
     // ── Tags ──────────────────────────────────────────────────────────────
 
+    /// Returns the existing tag's id if the name (ignoring case) is taken.
     pub fn create_tag(&self, name: &str, color: Option<&str>) -> Result<i64> {
-        tags::create(&self.conn, name, color)
+        self.write(|c| tags::create(c, name, color))
+    }
+
+    /// Find a tag by name (ignoring case) or create it. Returns the tag id.
+    pub fn find_or_create_tag(&self, name: &str) -> Result<i64> {
+        self.write(|c| tags::find_or_create(c, name))
     }
 
     pub fn get_tags(&self) -> Result<Vec<Tag>> {
-        tags::list(&self.conn)
+        self.read(tags::list)
+    }
+
+    /// Returns false if another tag already has the new name.
+    pub fn update_tag(&self, tag: &Tag) -> Result<bool> {
+        self.write(|c| tags::update(c, tag))
+    }
+
+    /// Re-tag all tasks from `source_ids` with `target_id` and delete the sources.
+    pub fn merge_tags(&self, source_ids: &[i64], target_id: i64) -> Result<()> {
+        self.write(|c| tags::merge(c, source_ids, target_id))
     }
 
     pub fn delete_tag(&self, id: i64) -> Result<()> {
-        tags::delete(&self.conn, id)
+        self.write(|c| tags::delete(c, id))
     }
 
     // ── Settings ──────────────────────────────────────────────────────────
 
     pub fn get_setting(&self, key: &str) -> Result<Option<String>> {
-        settings::get(&self.conn, key)
+        self.read(|c| settings::get(c, key))
     }
 
     pub fn set_setting(&self, key: &str, value: &str) -> Result<()> {
-        settings::set(&self.conn, key, value)
+        self.write(|c| settings::set(c, key, value))
+    }
+
+    pub fn delete_setting(&self, key: &str) -> Result<()> {
+        self.write(|c| settings::delete(c, key))
+    }
+
+    pub fn get_settings(&self) -> Result<Settings> {
+        self.read(settings::load)
+    }
+
+    /// Merge `changes` into the stored settings and save them in one write
+    /// transaction (see `Settings::merged`); a validation error is returned
+    /// without saving. Also applies the calendar settings to date bucketing.
+    pub fn update_settings(
+        &self,
+        changes: serde_json::Map<String, serde_json::Value>,
+        strict:  bool,
+    ) -> Result<std::result::Result<Settings, String>> {
+        let updated = self.write(|c| settings::update(c, changes, strict))?;
+        if let Ok(new) = &updated {
+            calendar::set(&self.calendar, Calendar::from_settings(new));
+        }
+        Ok(updated)
+    }
+
+    /// The timezone, day rollover and week start statistics use.
+    pub fn calendar(&self) -> Calendar {
+        calendar::current(&self.calendar)
+    }
+
+    // ── Secrets (ciphertext only — see crate::vault) ──────────────────────
+
+    pub fn get_secret(&self, name: &str) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        self.read(|c| secrets::get(c, name))
+    }
+
+    pub fn set_secret(&self, name: &str, nonce: &[u8], ciphertext: &[u8]) -> Result<()> {
+        self.write(|c| secrets::set(c, name, nonce, ciphertext))
+    }
+
+    pub fn delete_secret(&self, name: &str) -> Result<()> {
+        self.write(|c| secrets::delete(c, name))
+    }
+
+    pub fn get_secret_names(&self) -> Result<Vec<String>> {
+        self.read(secrets::names)
+    }
+
+    pub fn get_all_secrets(&self) -> Result<Vec<SecretRow>> {
+        self.read(secrets::all)
+    }
+
+    pub fn replace_secrets(&self, rows: &[SecretRow]) -> Result<()> {
+        self.write(|c| secrets::replace_all(c, rows))
+    }
+
+    /// Store an encrypted value and delete the plaintext setting it came from.
+    pub fn adopt_setting_as_secret(&self, name: &str, nonce: &[u8], ciphertext: &[u8]) -> Result<()> {
+        self.write(|c| secrets::adopt_setting(c, name, nonce, ciphertext))
     }
 
     // ── Tracked programs ──────────────────────────────────────────────────
 
     pub fn get_tracked_programs(&self) -> Result<Vec<TrackedProgram>> {
-        programs::list(&self.conn)
+        self.read(programs::list)
     }
 
     pub fn save_tracked_program(&self, program: TrackedProgram) -> Result<()> {
-        programs::upsert(&self.conn, &program)
+        self.write(|c| programs::upsert(c, &program))
     }
 
     pub fn remove_tracked_program(&self, id: i64) -> Result<()> {
-        programs::delete(&self.conn, id)
+        self.write(|c| programs::delete(c, id))
     }
 
     pub fn toggle_tracked_program(&self, id: i64, enabled: bool) -> Result<()> {
-        programs::set_enabled(&self.conn, id, enabled)
+        self.write(|c| programs::set_enabled(c, id, enabled))
+    }
+}
+
+/// Put freshly opened connections in place of the closed ones.
+fn install(
+    writer:  &mut Option<Connection>,
+    readers: &mut [MutexGuard<'_, Option<Connection>>],
+    (new_writer, new_readers): (Connection, Vec<Connection>),
+) {
+    *writer = Some(new_writer);
+    for (reader, conn) in readers.iter_mut().zip(new_readers) {
+        **reader = Some(conn);
     }
 }
 
@@ -256,6 +762,168 @@ mod schema {
             );
         ")?;
 
+        // v0.5.0 — encrypted integration credentials
+        conn.execute_batch("
+            CREATE TABLE IF NOT EXISTS secrets (
+                name       TEXT PRIMARY KEY,
+                nonce      BLOB NOT NULL,
+                ciphertext BLOB NOT NULL
+            );
+        ")?;
+
+        // v0.5.0 — indexes for large task lists and long session histories
+        conn.execute_batch("
+            CREATE INDEX IF NOT EXISTS idx_tasks_external     ON tasks(external_id, source);
+            CREATE INDEX IF NOT EXISTS idx_tasks_project      ON tasks(project_id);
+            CREATE INDEX IF NOT EXISTS idx_task_tags_tag      ON task_tags(tag_id);
+            CREATE INDEX IF NOT EXISTS idx_tasks_position     ON tasks(position, id DESC);
+            CREATE INDEX IF NOT EXISTS idx_sessions_timestamp ON pomodoro_sessions(timestamp);
+        ")?;
+
+        // v0.5.0 — subtask trees: effort estimates and fast child lookups
+        let _ = conn.execute("ALTER TABLE tasks ADD COLUMN estimate_minutes INTEGER", []);
+        conn.execute_batch("
+            CREATE INDEX IF NOT EXISTS idx_tasks_parent ON tasks(parent_id);
+        ")?;
+
+        // v0.5.0 — recurring tasks (JSON rule + link to the spawned copy)
+        let _ = conn.execute("ALTER TABLE tasks ADD COLUMN recurrence TEXT", []);
+        let _ = conn.execute("ALTER TABLE tasks ADD COLUMN next_occurrence_id INTEGER", []);
+
+        // v0.5.0 — blocked-by links between tasks
+        conn.execute_batch("
+            CREATE TABLE IF NOT EXISTS task_dependencies (
+                task_id    INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+                blocked_by INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+                PRIMARY KEY (task_id, blocked_by)
+            );
+            CREATE INDEX IF NOT EXISTS idx_task_dependencies_blocker ON task_dependencies(blocked_by);
+        ")?;
+
+        // v0.5.0 — project metadata, nesting and archiving
+        let _ = conn.execute("ALTER TABLE projects ADD COLUMN description TEXT", []);
+        let _ = conn.execute(
+            "ALTER TABLE projects ADD COLUMN parent_id INTEGER REFERENCES projects(id) ON DELETE SET NULL",
+            [],
+        );
+        let _ = conn.execute("ALTER TABLE projects ADD COLUMN client TEXT", []);
+        let _ = conn.execute("ALTER TABLE projects ADD COLUMN hourly_rate REAL", []);
+        let _ = conn.execute("ALTER TABLE projects ADD COLUMN currency TEXT", []);
+        let _ = conn.execute("ALTER TABLE projects ADD COLUMN archived INTEGER NOT NULL DEFAULT 0", []);
+
+        // v0.5.0 — project time budgets and the alerts already sent per period
+        let _ = conn.execute("ALTER TABLE projects ADD COLUMN budget_minutes INTEGER", []);
+        let _ = conn.execute("ALTER TABLE projects ADD COLUMN budget_period TEXT", []);
+        conn.execute_batch("
+            CREATE TABLE IF NOT EXISTS budget_alerts (
+                project_id INTEGER NOT NULL REFERENCES projects(id) ON DELETE CASCADE,
+                period     TEXT    NOT NULL,
+                threshold  INTEGER NOT NULL,
+                sent_at    INTEGER NOT NULL,
+                PRIMARY KEY (project_id, period, threshold)
+            );
+        ")?;
+
+        // v0.5.0 — per-project workflows. Done-ness is kept in completed_at
+        // from here on, so fill it in once for done tasks that predate it.
+        if conn.execute("ALTER TABLE projects ADD COLUMN workflow TEXT", []).is_ok() {
+            conn.execute_batch("
+                UPDATE tasks SET completed_at = created_at
+                WHERE status = 'done' AND completed_at IS NULL;
+            ")?;
+        }
+
+        // v0.5.0 — task templates (before the tag merge below, which re-links them)
+        conn.execute_batch("
+            CREATE TABLE IF NOT EXISTS task_templates (
+                id               INTEGER PRIMARY KEY AUTOINCREMENT,
+                name             TEXT    NOT NULL,
+                title            TEXT    NOT NULL,
+                description      TEXT,
+                project_id       INTEGER REFERENCES projects(id) ON DELETE SET NULL,
+                priority         INTEGER NOT NULL DEFAULT 0,
+                estimate_minutes INTEGER,
+                subtasks         TEXT    NOT NULL DEFAULT '[]',
+                created_at       INTEGER NOT NULL
+            );
+            CREATE TABLE IF NOT EXISTS task_template_tags (
+                template_id INTEGER NOT NULL REFERENCES task_templates(id) ON DELETE CASCADE,
+                tag_id      INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+                PRIMARY KEY (template_id, tag_id)
+            );
+        ")?;
+
+        // v0.5.0 — tag names are unique regardless of case
+        super::tags::merge_case_duplicates(conn)?;
+        conn.execute_batch("
+            CREATE UNIQUE INDEX IF NOT EXISTS idx_tags_name_nocase ON tags(name COLLATE NOCASE);
+        ")?;
+
+        // v0.5.0 — due-date reminders already sent
+        conn.execute_batch("
+            CREATE TABLE IF NOT EXISTS reminder_log (
+                task_id  INTEGER NOT NULL REFERENCES tasks(id) ON DELETE CASCADE,
+                kind     TEXT    NOT NULL,
+                due_date INTEGER NOT NULL,
+                sent_at  INTEGER NOT NULL,
+                PRIMARY KEY (task_id, kind, due_date)
+            );
+            CREATE INDEX IF NOT EXISTS idx_tasks_due ON tasks(due_date);
+        ")?;
+
+        // v0.5.0 — switches between the tasks of consecutive work sessions
+        conn.execute_batch("
+            CREATE TABLE IF NOT EXISTS task_switches (
+                id              INTEGER PRIMARY KEY AUTOINCREMENT,
+                from_task       TEXT    NOT NULL,
+                to_task         TEXT    NOT NULL,
+                switched_at     INTEGER NOT NULL,
+                mid_session     INTEGER NOT NULL,
+                seconds_on_task INTEGER NOT NULL
+            );
+            CREATE INDEX IF NOT EXISTS idx_task_switches_at ON task_switches(switched_at);
+        ")?;
+
+        // v0.5.0 — stats match sessions to the newest task with their title
+        conn.execute_batch("
+            CREATE INDEX IF NOT EXISTS idx_tasks_title ON tasks(title, id);
+        ")?;
+
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn failed_passphrase_change_keeps_the_data() {
+        let dir = std::env::temp_dir().join(format!("code-chrono-rekey-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("test.db").to_str().unwrap().to_string();
+
+        let db = Database::new(&path).unwrap();
+        db.create_project("kept", None).unwrap();
+
+        // A non-empty directory where the original is set aside makes the
+        // swap fail
+        std::fs::create_dir_all(format!("{path}.bak/blocker")).unwrap();
+        assert!(db.set_passphrase(Some("correct horse")).is_err());
+
+        assert!(!db.is_closed());
+        assert!(!db.is_encrypted());
+        assert!(!Database::is_encrypted_file(&path));
+        assert!(!std::path::Path::new(&format!("{path}.rekey")).exists());
+        db.create_project("after", None).unwrap();
+        assert_eq!(db.get_projects(false).unwrap().len(), 2);
+
+        // Nothing was left in memory only
+        drop(db);
+        let db = Database::new(&path).unwrap();
+        assert_eq!(db.get_projects(false).unwrap().len(), 2);
+        drop(db);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
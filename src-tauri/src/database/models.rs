@@ -3,6 +3,8 @@
 // Plain data structs that are shared across all database sub-modules
 // and serialised to/from the frontend via Tauri IPC.
 
+use std::collections::HashMap;
+
 use serde::{Deserialize, Serialize};
 
 // ── Session log ───────────────────────────────────────────────────────────
@@ -17,6 +19,16 @@ pub struct PomodoroRecord {
     pub timestamp: i64,
 }
 
+/// A completed work session and the project its task belongs to, as read
+/// by the billing module.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BillableSession {
+    pub task_name:  String,
+    pub project_id: Option<i64>,
+    pub elapsed:    i64,
+    pub timestamp:  i64,
+}
+
 // ── Tasks ─────────────────────────────────────────────────────────────────
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -35,6 +47,226 @@ pub struct Task {
     pub created_at:   i64,
     pub completed_at: Option<i64>,
     pub tags:         Vec<Tag>,
+    /// Planned effort in minutes; rolled up across subtasks by `get_task_tree`.
+    #[serde(default)]
+    pub estimate_minutes: Option<i64>,
+    /// When set, completing the task creates its next occurrence.
+    #[serde(default)]
+    pub recurrence: Option<Recurrence>,
+    /// Computed on read: some task this one is blocked by isn't done yet.
+    /// Ignored on write — links are managed with `add_task_dependency`.
+    #[serde(default)]
+    pub blocked: bool,
+}
+
+/// How a recurring task repeats. Stored as JSON in `tasks.recurrence`,
+/// e.g. `{"kind":"weekly","days":[0,2,4]}`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Recurrence {
+    /// Every day.
+    Daily,
+    /// Monday to Friday.
+    Weekdays,
+    /// On the listed weekdays, 0 = Monday … 6 = Sunday.
+    Weekly { days: Vec<u8> },
+    /// On this day of the month; short months use their last day.
+    Monthly { day: u8 },
+    /// `days` after the task was completed, whatever its due date was.
+    AfterCompletion { days: u32 },
+}
+
+/// "`task_id` is blocked by `blocked_by`".
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TaskDependency {
+    pub task_id:    i64,
+    pub blocked_by: i64,
+}
+
+/// Tasks and blocked-by links, returned by `get_dependency_graph`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct DependencyGraph {
+    pub tasks: Vec<Task>,
+    pub edges: Vec<TaskDependency>,
+}
+
+/// A task with its whole subtree, returned by `get_task_tree`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TaskNode {
+    pub task:     Task,
+    pub children: Vec<TaskNode>,
+    pub rollup:   TaskRollup,
+}
+
+/// Totals for a task together with everything beneath it.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TaskRollup {
+    /// Seconds of completed sessions logged against the task and its subtasks.
+    pub tracked_seconds:  i64,
+    /// Own estimate plus the estimates of all subtasks, in minutes.
+    pub estimate_minutes: i64,
+    /// Number of subtasks at any depth, and how many of them are done.
+    pub subtasks_total:   i64,
+    pub subtasks_done:    i64,
+    /// Share of subtasks done (0–100), or `None` for a leaf task.
+    pub percent_done:     Option<f64>,
+}
+
+/// Result of `complete_task`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TaskCompletion {
+    pub completed:     bool,
+    /// Subtasks that were still open: completed along with the parent when
+    /// requested, otherwise the reason `completed` is false.
+    pub open_subtasks: Vec<Task>,
+}
+
+/// One change applied to every task of a `batch_update_tasks` call,
+/// e.g. `{"op":"set_status","status":"done"}`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum BatchOperation {
+    SetStatus   { status: String },
+    SetProject  { project_id: Option<i64> },
+    SetPriority { priority: i32 },
+    SetDueDate  { due_date: Option<i64> },
+    AddTags     { tag_ids: Vec<i64> },
+    RemoveTags  { tag_ids: Vec<i64> },
+    Delete,
+}
+
+/// Outcome for one task id of a batch.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BatchResult {
+    pub id:    i64,
+    pub ok:    bool,
+    pub error: Option<String>,
+}
+
+/// A reusable task structure. `{placeholders}` in the title, description
+/// and subtask titles are filled in by `instantiate_template`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TaskTemplate {
+    pub id:          i64,
+    pub name:        String,
+    /// Title pattern, e.g. "Release {version}".
+    pub title:       String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub project_id:  Option<i64>,
+    #[serde(default)]
+    pub priority:    i32,
+    #[serde(default)]
+    pub estimate_minutes: Option<i64>,
+    #[serde(default)]
+    pub tags:        Vec<Tag>,
+    /// Stored as JSON in `task_templates.subtasks`.
+    #[serde(default)]
+    pub subtasks:    Vec<TemplateSubtask>,
+    #[serde(default)]
+    pub created_at:  i64,
+}
+
+/// One checklist item of a template; items can nest.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TemplateSubtask {
+    pub title: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub estimate_minutes: Option<i64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub subtasks: Vec<TemplateSubtask>,
+}
+
+/// A due-date notification that is due to be shown.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Reminder {
+    pub task_id:  i64,
+    pub title:    String,
+    pub due_date: i64,
+    pub kind:     ReminderKind,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ReminderKind {
+    /// The task is due within `minutes_before` minutes.
+    Upcoming { minutes_before: u32 },
+    Overdue,
+}
+
+/// Structured filter for `query_tasks`. Every field is optional; an empty
+/// query returns all tasks in the default (board) order.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+#[serde(default)]
+pub struct TaskQuery {
+    /// Match tasks in any of these statuses.
+    pub statuses:     Vec<String>,
+    /// Match tasks in any of these projects.
+    pub project_ids:  Vec<i64>,
+    pub tag_ids:      Vec<i64>,
+    /// Whether a task needs any or all of `tag_ids`.
+    pub tag_match:    TagMatch,
+    /// Inclusive due-date window (unix seconds).
+    pub due_after:    Option<i64>,
+    pub due_before:   Option<i64>,
+    /// Only tasks whose due date has passed and that aren't done.
+    pub overdue:      bool,
+    pub priority_min: Option<i32>,
+    pub priority_max: Option<i32>,
+    /// Integration sources ("GitHub", "GitLab", "Jira"); "local" matches
+    /// tasks that weren't imported.
+    pub sources:      Vec<String>,
+    /// `Some(true)` → subtasks only, `Some(false)` → top-level tasks only.
+    pub has_parent:   Option<bool>,
+    /// Only tasks that can be worked on now: not done and not blocked.
+    pub actionable:   bool,
+    /// Substring match on title and description.
+    pub text:         Option<String>,
+    pub sort:         TaskSort,
+    pub direction:    SortDirection,
+    /// Opaque cursor from a previous `TaskPage::next_cursor`.
+    pub cursor:       Option<String>,
+    /// Page size. `None` returns every match.
+    pub limit:        Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TagMatch {
+    #[default]
+    Any,
+    All,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskSort {
+    #[default]
+    Position,
+    CreatedAt,
+    DueDate,
+    Priority,
+    Title,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SortDirection {
+    #[default]
+    Asc,
+    Desc,
+}
+
+/// One page of `query_tasks` results.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TaskPage {
+    pub tasks:       Vec<Task>,
+    /// Pass back as `TaskQuery::cursor` to fetch the next page;
+    /// `None` when this is the last one.
+    pub next_cursor: Option<String>,
 }
 
 // ── Projects & Tags ───────────────────────────────────────────────────────
@@ -44,6 +276,115 @@ pub struct Project {
     pub id:    i64,
     pub name:  String,
     pub color: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    /// Optional parent project, for grouping.
+    #[serde(default)]
+    pub parent_id:   Option<i64>,
+    #[serde(default)]
+    pub client:      Option<String>,
+    /// Billable rate per hour, in `currency`.
+    #[serde(default)]
+    pub hourly_rate: Option<f64>,
+    /// ISO 4217 code, e.g. "EUR".
+    #[serde(default)]
+    pub currency:    Option<String>,
+    /// Hidden from `get_projects` and pickers; tasks and stats are kept.
+    #[serde(default)]
+    pub archived:    bool,
+    /// Time budget in minutes, covering sub-projects too. `None` = no budget.
+    #[serde(default)]
+    pub budget_minutes: Option<i64>,
+    #[serde(default)]
+    pub budget_period:  BudgetPeriod,
+}
+
+/// Outcome of `update_project`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProjectUpdate {
+    Updated,
+    /// Another project already has the name (ignoring case).
+    NameTaken,
+    /// The new parent is the project itself or one of its sub-projects.
+    ParentCycle,
+}
+
+/// A project's status columns, in board order. Stored as JSON in
+/// `projects.workflow`; projects without one use `Workflow::default()`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Workflow {
+    pub statuses: Vec<WorkflowStatus>,
+    /// Allowed moves between different statuses. Empty allows any move.
+    #[serde(default)]
+    pub transitions: Vec<WorkflowTransition>,
+    /// External status (GitLab state, Jira status category, …) → status key,
+    /// matched ignoring case. Unmapped statuses fall back to built-in guesses.
+    #[serde(default)]
+    pub import_map: HashMap<String, String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WorkflowStatus {
+    /// Stored in `tasks.status`.
+    pub key:  String,
+    pub name: String,
+    /// Tasks in this status count as completed.
+    #[serde(default)]
+    pub done: bool,
+    /// Most tasks of the project allowed in this status at once.
+    #[serde(default)]
+    pub wip_limit: Option<u32>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct WorkflowTransition {
+    pub from: String,
+    pub to:   String,
+}
+
+/// Outcome of checking a task's status change against its workflow.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StatusChange {
+    Allowed,
+    /// The status isn't one of the workflow's columns.
+    UnknownStatus,
+    /// The workflow has no transition from the current status.
+    NotAllowed,
+    /// The target column is at its WIP limit.
+    WipLimitReached,
+}
+
+/// Whether a project's budget is for its whole life or for each calendar month.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum BudgetPeriod {
+    #[default]
+    Total,
+    Monthly,
+}
+
+/// How much of a project's budget is used, returned by `get_budget_status`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BudgetStatus {
+    pub project_id:       i64,
+    pub project_name:     String,
+    pub budget_minutes:   i64,
+    pub period:           BudgetPeriod,
+    /// Start of the current month (unix seconds) for a monthly budget.
+    pub period_start:     Option<i64>,
+    /// Completed sessions on the project's tasks (and its sub-projects').
+    pub consumed_seconds: i64,
+    /// Negative once the budget is overrun.
+    pub remaining_seconds: i64,
+    pub percent_used:     f64,
+}
+
+/// A budget threshold crossed for the first time in the current period.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BudgetAlert {
+    pub status:    BudgetStatus,
+    /// Percentage from `Settings::budget_alert_thresholds`.
+    pub threshold: u32,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -72,3 +413,246 @@ pub struct DailyStats {
     pub sessions:      i64,
     pub total_seconds: i64,
 }
+
+/// What `get_grouped_stats` groups work sessions by.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StatsGroup {
+    Project,
+    Tag,
+    Source,
+    Status,
+}
+
+/// Totals for one group, returned by `get_grouped_stats`. The bucket with
+/// `unassigned` set holds sessions that match no task, or whose task has no
+/// project / tag / source; its name is "Unassigned".
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct GroupStats {
+    /// Project or tag id; `None` for sources, statuses and the unassigned bucket
+    pub id:            Option<i64>,
+    /// Project or tag name, source (e.g. "github") or status key
+    pub name:          String,
+    pub unassigned:    bool,
+    pub sessions:      i64,
+    pub total_seconds: i64,
+}
+
+/// The period length of `get_trends` and `get_trend_series`.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrendPeriod {
+    Week,
+    Month,
+}
+
+/// Work totals for one week or month, returned by `get_trends`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PeriodTrend {
+    /// First day of the week ("YYYY-MM-DD") or the month ("YYYY-MM")
+    pub period:                      String,
+    pub sessions:                    i64,
+    pub total_seconds:               i64,
+    /// Days with at least one completed work session
+    pub working_days:                i64,
+    pub avg_seconds_per_working_day: i64,
+    pub previous_seconds:            i64,
+    /// The same week (52 weeks earlier) or month one year earlier
+    pub last_year_seconds:           i64,
+    /// `total_seconds - previous_seconds`
+    pub delta_previous:              i64,
+    /// `total_seconds - last_year_seconds`
+    pub delta_last_year:             i64,
+}
+
+/// Work time by weekday and hour of day, returned by `get_focus_heatmap`.
+/// Rows follow the configured week start; columns are local clock hours.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct FocusHeatmap {
+    /// Weekday of each row, 0 = Monday … 6 = Sunday
+    pub weekdays: [u32; 7],
+    pub minutes:  [[f64; 24]; 7],
+    /// Sessions by the cell they started in
+    pub sessions: [[i64; 24]; 7],
+}
+
+/// How a work session ended.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionOutcome {
+    /// The timer ran out
+    Completed,
+    /// Stopped early with `reset_timer`
+    Reset,
+    /// Cut short by starting a session on another task
+    Switched,
+    /// No end was logged, e.g. a break was started or the app closed
+    Unfinished,
+}
+
+/// Focus metrics of one work session, part of `FocusReport`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FocusSession {
+    pub task_name:           String,
+    pub started_at:          i64,
+    pub ended_at:            i64,
+    pub outcome:             SessionOutcome,
+    pub focus_seconds:       i64,
+    pub pauses:              i64,
+    pub pause_seconds:       i64,
+    /// Average time between (re)starting and the next pause or the end
+    pub avg_stretch_seconds: i64,
+    /// 0–100, see `database/focus.rs`
+    pub focus_score:         u32,
+}
+
+/// Focus metrics of one day's work sessions, part of `FocusReport`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FocusDay {
+    /// ISO date "YYYY-MM-DD"
+    pub day:                 String,
+    pub sessions:            i64,
+    pub completed:           i64,
+    pub reset:               i64,
+    pub switched:            i64,
+    pub focus_seconds:       i64,
+    pub pauses:              i64,
+    pub pause_seconds:       i64,
+    pub avg_stretch_seconds: i64,
+    pub focus_score:         u32,
+}
+
+/// Returned by `get_focus_metrics`; sessions oldest first, days in order.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct FocusReport {
+    pub sessions: Vec<FocusSession>,
+    pub days:     Vec<FocusDay>,
+}
+
+/// Task switches on one day, part of `SwitchReport`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SwitchDay {
+    /// ISO date "YYYY-MM-DD"
+    pub day:         String,
+    pub switches:    i64,
+    /// Switches that cut a running work session short
+    pub mid_session: i64,
+}
+
+/// How often work went from one task straight to another.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SwitchPair {
+    pub from_task: String,
+    pub to_task:   String,
+    pub count:     i64,
+}
+
+/// Returned by `get_switch_stats`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SwitchReport {
+    pub switches:                  i64,
+    pub mid_session:               i64,
+    /// Average work time on a task between switching to it and away from it
+    pub avg_seconds_before_switch: i64,
+    pub days:                      Vec<SwitchDay>,
+    /// Most frequent first
+    pub top_pairs:                 Vec<SwitchPair>,
+}
+
+/// What `get_trend_series` draws one line per.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SeriesBy {
+    Task,
+    Project,
+}
+
+/// Per-task or per-project work time per period, returned by
+/// `get_trend_series`. Every line has one value per entry of `periods`.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TrendSeries {
+    pub periods: Vec<String>,
+    pub lines:   Vec<SeriesLine>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SeriesLine {
+    /// Task or project id; `None` for the unassigned line and unknown tasks
+    pub id:         Option<i64>,
+    /// Task title or project name; "Unassigned" for the unassigned line
+    pub name:       String,
+    pub unassigned: bool,
+    pub seconds:    Vec<i64>,
+}
+
+// ── Settings ──────────────────────────────────────────────────────────────
+
+/// Every app setting, with its type and default. Each field is stored as its
+/// own row in the settings table under the field name; parsing and
+/// validation live in `database/settings.rs`. API tokens are not settings —
+/// they live in the encrypted vault (see `crate::vault`).
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(default)]
+pub struct Settings {
+    // Notifications
+    pub notifications_enabled:  bool,
+    pub notify_on_timer_start:  bool,
+    pub notify_on_timer_end:    bool,
+    pub notify_break_recommend: bool,
+    pub notify_on_program_open: bool,
+    pub notify_budget_alerts:   bool,
+    pub notify_due_reminders:   bool,
+
+    // Calendar (how statistics bucket time into days, weeks and months)
+    /// IANA timezone such as "Europe/Berlin", or empty for the system zone.
+    pub timezone: String,
+    /// Hour (0–23) at which a new day starts; work before it counts
+    /// towards the previous day.
+    pub day_rollover_hour: u32,
+    /// First day of the week, 0 = Monday … 6 = Sunday.
+    pub week_start: u32,
+
+    // Reminders
+    /// Minutes before a task's due date at which to remind, e.g. `[1440, 60]`.
+    pub due_reminder_offsets: Vec<u32>,
+
+    // Budgets
+    /// Percentages of a project budget at which to notify, e.g. `[75, 100]`.
+    pub budget_alert_thresholds: Vec<u32>,
+
+    // Integrations
+    pub auto_import_projects: bool,
+    /// "owner/repo", or empty for every repo the token can see.
+    pub github_repo: String,
+    pub gitlab_host: String,
+    pub jira_domain: String,
+    pub jira_email:  String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            notifications_enabled:  true,
+            notify_on_timer_start:  true,
+            notify_on_timer_end:    true,
+            notify_break_recommend: true,
+            notify_on_program_open: true,
+            notify_budget_alerts:   true,
+            notify_due_reminders:   true,
+
+            timezone:          String::new(),
+            day_rollover_hour: 0,
+            week_start:        0,
+
+            due_reminder_offsets: vec![1440, 60],
+
+            budget_alert_thresholds: vec![75, 100],
+
+            auto_import_projects: true,
+            github_repo: String::new(),
+            gitlab_host: "https://gitlab.com".to_string(),
+            jira_domain: String::new(),
+            jira_email:  String::new(),
+        }
+    }
+}
@@ -0,0 +1,90 @@
+// database/pool.rs
+//
+// Connection setup and the small read-only pool.
+//
+// The database runs in WAL mode so readers never block the writer (and vice
+// versa). All writes go through a single dedicated connection owned by
+// `Database`; reads are spread over a handful of `query_only` connections
+// so a long stats query can't hold up the timer's session logging.
+//
+// Connections are `None` while the database is closed, e.g. after a failed
+// passphrase change (see `Database::set_passphrase`); every call then fails
+// with `closed()` until it is unlocked again.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+
+use rusqlite::{Connection, Error, Result};
+
+use super::calendar::{self, SharedCalendar};
+use super::cipher;
+
+/// Number of read-only connections kept open alongside the writer.
+pub const READERS: usize = 4;
+
+/// Open a connection with the pragmas and calendar SQL functions every
+/// connection needs, unlocking it first when the database is encrypted.
+pub fn open(path: &str, passphrase: Option<&str>, calendar: &SharedCalendar) -> Result<Connection> {
+    let conn = Connection::open(path)?;
+    if let Some(passphrase) = passphrase {
+        cipher::apply_key(&conn, passphrase)?;
+    }
+    conn.busy_timeout(Duration::from_secs(5))?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.pragma_update(None, "synchronous", "NORMAL")?;
+    calendar::register(&conn, calendar)?;
+    Ok(conn)
+}
+
+/// The error returned for every call while the database is closed.
+pub fn closed() -> Error {
+    Error::SqliteFailure(
+        rusqlite::ffi::Error::new(rusqlite::ffi::SQLITE_CANTOPEN),
+        Some("The database is closed; unlock it again to continue.".into()),
+    )
+}
+
+/// A fixed set of read-only connections handed out round-robin.
+pub struct ReadPool {
+    conns: Vec<Mutex<Option<Connection>>>,
+    next:  AtomicUsize,
+}
+
+impl ReadPool {
+    pub fn open(path: &str, size: usize, passphrase: Option<&str>, calendar: &SharedCalendar) -> Result<Self> {
+        let conns = (0..size.max(1))
+            .map(|_| open_reader(path, passphrase, calendar).map(|c| Mutex::new(Some(c))))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(ReadPool { conns, next: AtomicUsize::new(0) })
+    }
+
+    /// Lock every reader, e.g. to close and reopen them around a file swap.
+    pub fn lock_all(&self) -> Vec<MutexGuard<'_, Option<Connection>>> {
+        self.conns
+            .iter()
+            .map(|c| c.lock().unwrap_or_else(PoisonError::into_inner))
+            .collect()
+    }
+
+    /// Run `f` on an idle reader, or wait for the next one in rotation if
+    /// they are all busy.
+    pub fn with<T>(&self, f: impl FnOnce(&Connection) -> Result<T>) -> Result<T> {
+        for conn in &self.conns {
+            if let Ok(conn) = conn.try_lock() {
+                return f(conn.as_ref().ok_or_else(closed)?);
+            }
+        }
+        let idx  = self.next.fetch_add(1, Ordering::Relaxed) % self.conns.len();
+        let conn = self.conns[idx].lock().unwrap_or_else(PoisonError::into_inner);
+        f(conn.as_ref().ok_or_else(closed)?)
+    }
+}
+
+/// Open a read-only pool member.
+pub fn open_reader(path: &str, passphrase: Option<&str>, calendar: &SharedCalendar) -> Result<Connection> {
+    let conn = open(path, passphrase, calendar)?;
+    // Any accidental write through a reader fails loudly
+    conn.pragma_update(None, "query_only", true)?;
+    Ok(conn)
+}
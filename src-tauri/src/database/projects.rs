@@ -1,9 +1,31 @@
 // database/projects.rs — Project CRUD
+//
+// Besides name and colour a project carries billing metadata (client,
+// hourly rate, currency), a description, an optional parent for grouping,
+// and an `archived` flag. Archived projects are left out of `list` unless
+// asked for, but their tasks and sessions are untouched, so statistics
+// still include them.
+//
+// Project names are unique regardless of case. Unlike tags this is checked
+// here rather than by an index, so existing duplicates keep working until
+// they are merged.
 
-use rusqlite::{Connection, Result};
-use super::models::Project;
+use rusqlite::{Connection, OptionalExtension, Result, Row};
+use super::budgets::{period_from_column, period_to_column};
+use super::models::{Project, ProjectUpdate};
+use super::workflows;
 
+const PROJECT_COLUMNS: &str =
+    "id, name, color, description, parent_id, client, hourly_rate, currency, archived,
+     budget_minutes, budget_period";
+
+/// Create a project and return its id. If a project with the same name
+/// (ignoring case) already exists, its id is returned and it is left unchanged.
 pub fn create(conn: &Connection, name: &str, color: Option<&str>) -> Result<i64> {
+    let name = name.trim();
+    if let Some(id) = find(conn, name)? {
+        return Ok(id);
+    }
     conn.execute(
         "INSERT INTO projects (name, color) VALUES (?1, ?2)",
         (name, color),
@@ -11,32 +33,219 @@ pub fn create(conn: &Connection, name: &str, color: Option<&str>) -> Result<i64>
     Ok(conn.last_insert_rowid())
 }
 
-/// Return the id of the project with the given name, creating it if it does not exist.
+/// Return the id of the project with the given name (ignoring case),
+/// creating it if it does not exist.
 pub fn find_or_create(conn: &Connection, name: &str) -> Result<i64> {
-    let existing: Option<i64> = conn
-        .prepare("SELECT id FROM projects WHERE name = ?1")?
-        .query_row([name], |row| row.get(0))
-        .ok();
-    if let Some(id) = existing {
+    let name = name.trim();
+    if let Some(id) = find(conn, name)? {
         return Ok(id);
     }
-    create(conn, name, None)
+    conn.execute("INSERT INTO projects (name) VALUES (?1)", [name])?;
+    Ok(conn.last_insert_rowid())
 }
 
-pub fn list(conn: &Connection) -> Result<Vec<Project>> {
-    let mut stmt = conn.prepare("SELECT id, name, color FROM projects ORDER BY name")?;
+/// All projects ordered by name; archived ones only when `include_archived`.
+pub fn list(conn: &Connection, include_archived: bool) -> Result<Vec<Project>> {
+    let sql = format!(
+        "SELECT {PROJECT_COLUMNS} FROM projects
+         WHERE ?1 OR archived = 0
+         ORDER BY name"
+    );
+    let mut stmt = conn.prepare(&sql)?;
     let rows = stmt
-        .query_map([], |row| {
-            Ok(Project { id: row.get(0)?, name: row.get(1)?, color: row.get(2)? })
-        })?
+        .query_map([include_archived], map_project)?
         .filter_map(Result::ok)
         .collect();
     Ok(rows)
 }
 
-/// Tasks whose project_id matches are automatically un-assigned
-/// via the ON DELETE SET NULL foreign key constraint.
-pub fn delete(conn: &Connection, id: i64) -> Result<()> {
-    conn.execute("DELETE FROM projects WHERE id = ?1", [id])?;
+pub fn get(conn: &Connection, id: i64) -> Result<Option<Project>> {
+    let sql = format!("SELECT {PROJECT_COLUMNS} FROM projects WHERE id = ?1");
+    conn.query_row(&sql, [id], map_project).optional()
+}
+
+/// Overwrite every editable field of a project. Nothing changes if the new
+/// name is taken by another project or the new parent is the project itself
+/// or one of its sub-projects; fails with `QueryReturnedNoRows` if the
+/// project is missing. Changing the budget re-arms its threshold alerts.
+pub fn update(conn: &Connection, project: &Project) -> Result<ProjectUpdate> {
+    let tx = conn.unchecked_transaction()?;
+    tx.query_row("SELECT 1 FROM projects WHERE id = ?1", [project.id], |_| Ok(()))?;
+    if find(&tx, &project.name)?.is_some_and(|id| id != project.id) {
+        return Ok(ProjectUpdate::NameTaken);
+    }
+    if let Some(parent_id) = project.parent_id {
+        if subtree(&tx, project.id)?.contains(&parent_id) {
+            return Ok(ProjectUpdate::ParentCycle);
+        }
+    }
+
+    let period = period_to_column(project.budget_period);
+    tx.execute(
+        "DELETE FROM budget_alerts
+         WHERE project_id = ?1
+           AND EXISTS (SELECT 1 FROM projects WHERE id = ?1
+                       AND (budget_minutes IS NOT ?2 OR budget_period IS NOT ?3))",
+        (project.id, project.budget_minutes, period),
+    )?;
+    tx.execute(
+        "UPDATE projects
+         SET name = ?1, color = ?2, description = ?3, parent_id = ?4,
+             client = ?5, hourly_rate = ?6, currency = ?7, archived = ?8,
+             budget_minutes = ?9, budget_period = ?10
+         WHERE id = ?11",
+        (
+            &project.name, &project.color, &project.description, project.parent_id,
+            &project.client, project.hourly_rate, &project.currency, project.archived,
+            project.budget_minutes, period, project.id,
+        ),
+    )?;
+    tx.commit()?;
+    Ok(ProjectUpdate::Updated)
+}
+
+/// Rename a project. Returns `Ok(false)` if another project already has the
+/// name (ignoring case); fails with `QueryReturnedNoRows` if it is missing.
+pub fn rename(conn: &Connection, id: i64, name: &str) -> Result<bool> {
+    let name = name.trim();
+    if find(conn, name)?.is_some_and(|other| other != id) {
+        return Ok(false);
+    }
+    if conn.execute("UPDATE projects SET name = ?1 WHERE id = ?2", (name, id))? == 0 {
+        return Err(rusqlite::Error::QueryReturnedNoRows);
+    }
+    Ok(true)
+}
+
+/// Move every task, template and sub-project of `source_ids` into `target_id`, then
+/// delete the sources, in one transaction. Moved tasks are fitted to the
+/// target's workflow (see `workflows::adopt_tasks`). Returns the number of
+/// tasks moved, or `Ok(None)` without changing anything if the target is one
+/// of the sources' sub-projects. Fails with `QueryReturnedNoRows` if any
+/// project is missing.
+pub fn merge(conn: &Connection, source_ids: &[i64], target_id: i64) -> Result<Option<usize>> {
+    let tx = conn.unchecked_transaction()?;
+    tx.query_row("SELECT 1 FROM projects WHERE id = ?1", [target_id], |_| Ok(()))?;
+
+    let mut moved = 0;
+    for &source_id in source_ids.iter().filter(|id| **id != target_id) {
+        if subtree(&tx, source_id)?.contains(&target_id) {
+            return Ok(None);
+        }
+        moved += tx.execute(
+            "UPDATE tasks SET project_id = ?2 WHERE project_id = ?1",
+            (source_id, target_id),
+        )?;
+        tx.execute(
+            "UPDATE task_templates SET project_id = ?2 WHERE project_id = ?1",
+            (source_id, target_id),
+        )?;
+        tx.execute(
+            "UPDATE projects SET parent_id = ?2 WHERE parent_id = ?1",
+            (source_id, target_id),
+        )?;
+        if tx.execute("DELETE FROM projects WHERE id = ?1", [source_id])? == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+    }
+    workflows::adopt_tasks(&tx, Some(target_id))?;
+    tx.commit()?;
+    Ok(Some(moved))
+}
+
+/// Archive or restore a project together with all of its sub-projects.
+pub fn set_archived(conn: &Connection, id: i64, archived: bool) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    let ids = subtree(&tx, id)?;
+    if ids.is_empty() {
+        return Err(rusqlite::Error::QueryReturnedNoRows);
+    }
+    let mut stmt = tx.prepare("UPDATE projects SET archived = ?1 WHERE id = ?2")?;
+    for project_id in ids {
+        stmt.execute((archived, project_id))?;
+    }
+    drop(stmt);
+    tx.commit()
+}
+
+/// Check the fields a user can type in. Trims text fields and upper-cases
+/// the currency code in place.
+pub fn validate(project: &mut Project) -> std::result::Result<(), String> {
+    project.name = project.name.trim().to_string();
+    if project.name.is_empty() {
+        return Err("Project name must not be empty.".into());
+    }
+    for field in [&mut project.description, &mut project.client] {
+        *field = field.take().map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+    }
+    if let Some(rate) = project.hourly_rate {
+        if !rate.is_finite() || rate < 0.0 {
+            return Err("Hourly rate must be zero or more.".into());
+        }
+    }
+    if project.budget_minutes.is_some_and(|m| m <= 0) {
+        return Err("Budget must be at least one minute.".into());
+    }
+    if let Some(currency) = project.currency.take() {
+        let code = currency.trim().to_uppercase();
+        if !code.is_empty() {
+            if code.len() != 3 || !code.chars().all(|c| c.is_ascii_alphabetic()) {
+                return Err("Currency must be a three-letter code such as EUR or USD.".into());
+            }
+            project.currency = Some(code);
+        }
+    }
     Ok(())
 }
+
+/// Sub-projects are re-parented to the top level and tasks whose project_id
+/// matches are un-assigned, both via ON DELETE SET NULL foreign keys. The
+/// un-assigned tasks are then fitted to the default workflow.
+pub fn delete(conn: &Connection, id: i64) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute("DELETE FROM projects WHERE id = ?1", [id])?;
+    workflows::adopt_tasks(&tx, None)?;
+    tx.commit()
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+/// The oldest project with this name, ignoring case.
+fn find(conn: &Connection, name: &str) -> Result<Option<i64>> {
+    conn.query_row(
+        "SELECT id FROM projects WHERE name = ?1 COLLATE NOCASE ORDER BY id LIMIT 1",
+        [name.trim()],
+        |row| row.get(0),
+    )
+    .optional()
+}
+
+/// `id` and the ids of every project beneath it; empty if `id` doesn't exist.
+fn subtree(conn: &Connection, id: i64) -> Result<Vec<i64>> {
+    let mut stmt = conn.prepare(
+        "WITH RECURSIVE sub(id) AS (
+             SELECT id FROM projects WHERE id = ?1
+             UNION
+             SELECT p.id FROM projects p JOIN sub ON p.parent_id = sub.id
+         )
+         SELECT id FROM sub",
+    )?;
+    let ids = stmt.query_map([id], |row| row.get(0))?.filter_map(Result::ok).collect();
+    Ok(ids)
+}
+
+fn map_project(row: &Row) -> Result<Project> {
+    Ok(Project {
+        id:          row.get(0)?,
+        name:        row.get(1)?,
+        color:       row.get(2)?,
+        description: row.get(3)?,
+        parent_id:   row.get(4)?,
+        client:      row.get(5)?,
+        hourly_rate: row.get(6)?,
+        currency:    row.get(7)?,
+        archived:    row.get(8)?,
+        budget_minutes: row.get(9)?,
+        budget_period:  period_from_column(row.get(10)?),
+    })
+}
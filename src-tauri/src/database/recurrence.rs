@@ -0,0 +1,230 @@
+// database/recurrence.rs
+//
+// Recurring tasks. A task's rule lives as JSON in `tasks.recurrence`; when
+// the task is completed, `spawn_next` creates the next occurrence with a
+// freshly computed due date, copying its project, tags, description and
+// whole subtree. `next_occurrence_id` on the completed task records the
+// copy so re-completing it later doesn't spawn a duplicate.
+
+use std::collections::hash_map::Entry;
+use std::collections::HashMap;
+
+use chrono::{Datelike, Days, Duration, NaiveDate, Utc};
+use rusqlite::{Connection, OptionalExtension, Result};
+
+use super::calendar::Calendar;
+use super::models::{Recurrence, Task};
+use super::{task_tree, tasks, workflows};
+
+/// Search window for calendar rules; every valid rule matches within it.
+const MAX_LOOKAHEAD_DAYS: u64 = 400;
+
+/// Serialise a rule for the `recurrence` column.
+pub fn to_column(rule: Option<&Recurrence>) -> Option<String> {
+    rule.and_then(|r| serde_json::to_string(r).ok())
+}
+
+/// Parse the `recurrence` column. Unreadable rules are treated as none.
+pub fn from_column(raw: Option<String>) -> Option<Recurrence> {
+    raw.and_then(|r| serde_json::from_str(&r).ok())
+}
+
+/// Check a rule's parameters before it is saved.
+pub fn validate(rule: &Recurrence) -> std::result::Result<(), String> {
+    match rule {
+        Recurrence::Weekly { days } if days.is_empty() || days.iter().any(|d| *d > 6) => {
+            Err("Weekly recurrence needs weekdays between 0 (Monday) and 6 (Sunday).".into())
+        }
+        Recurrence::Monthly { day } if !(1..=31).contains(day) => {
+            Err("Monthly recurrence needs a day between 1 and 31.".into())
+        }
+        Recurrence::AfterCompletion { days: 0 } => {
+            Err("Recurrence after completion needs at least one day.".into())
+        }
+        _ => Ok(()),
+    }
+}
+
+/// Create the next occurrence of `id` if it recurs and hasn't spawned one
+/// yet, with dates in `calendar`'s timezone. Returns the new task's id.
+pub fn spawn_next(conn: &Connection, calendar: &Calendar, id: i64) -> Result<Option<i64>> {
+    let row = conn
+        .query_row(
+            "SELECT t.recurrence, t.due_date, t.completed_at, n.id IS NOT NULL
+             FROM tasks t LEFT JOIN tasks n ON n.id = t.next_occurrence_id
+             WHERE t.id = ?1",
+            [id],
+            |row| {
+                Ok((
+                    from_column(row.get(0)?),
+                    row.get::<_, Option<i64>>(1)?,
+                    row.get::<_, Option<i64>>(2)?,
+                    row.get::<_, bool>(3)?,
+                ))
+            },
+        )
+        .optional()?;
+    let Some((Some(rule), due, completed_at, already_spawned)) = row else { return Ok(None) };
+    if already_spawned {
+        return Ok(None);
+    }
+
+    let completed_at = completed_at.unwrap_or_else(|| Utc::now().timestamp());
+    let Some(next_due) = next_due(calendar, &rule, due, completed_at) else { return Ok(None) };
+    // Subtask due dates keep their offset from the parent's
+    let shift = due.map(|d| next_due - d);
+
+    let mut initial_status: HashMap<Option<i64>, String> = HashMap::new();
+    let mut copies: HashMap<i64, i64> = HashMap::new();
+    for task in task_tree::descendants(conn, id, true)? {
+        let status = match initial_status.entry(task.project_id) {
+            Entry::Occupied(e) => e.get().clone(),
+            Entry::Vacant(e) => {
+                let workflow = workflows::for_project(conn, task.project_id)?;
+                e.insert(workflow.initial_status().to_string()).clone()
+            }
+        };
+        let is_root = task.id == id;
+        let parent_id = if is_root {
+            task.parent_id
+        } else {
+            task.parent_id.and_then(|p| copies.get(&p).copied())
+        };
+        let due_date = if is_root {
+            Some(next_due)
+        } else {
+            task.due_date.zip(shift).map(|(d, s)| d + s)
+        };
+        let old_id = task.id;
+        let copy = Task {
+            id: 0,
+            status,
+            parent_id,
+            due_date,
+            external_id: None,
+            source: None,
+            completed_at: None,
+            ..task
+        };
+        copies.insert(old_id, tasks::create(conn, copy)?);
+    }
+
+    let next_id = copies[&id];
+    conn.execute("UPDATE tasks SET next_occurrence_id = ?1 WHERE id = ?2", (next_id, id))?;
+    Ok(Some(next_id))
+}
+
+/// Due date of the occurrence after one due at `due` (if any) and
+/// completed at `completed_at`, keeping the original time of day in
+/// `calendar`'s timezone.
+///
+/// Calendar rules pick the first matching day that is after the previous
+/// due date and whose occurrence falls after the completion, so finishing
+/// late doesn't leave a trail of already-overdue occurrences. When the time
+/// of day is skipped by a DST change, the occurrence moves forward by the
+/// length of the gap (usually one hour).
+pub fn next_due(calendar: &Calendar, rule: &Recurrence, due: Option<i64>, completed_at: i64) -> Option<i64> {
+    let done = calendar.local(completed_at);
+    let base = due.map_or(done, |due| calendar.local(due));
+    let at = |date: NaiveDate| {
+        let local = date.and_time(base.time());
+        (0..3).find_map(|h| calendar.timestamp(local + Duration::hours(h)))
+    };
+
+    match rule {
+        Recurrence::AfterCompletion { days } => {
+            at(done.date().checked_add_days(Days::new(u64::from(*days)))?)
+        }
+        _ => {
+            let earliest = base.date().succ_opt()?.max(done.date());
+            (0..MAX_LOOKAHEAD_DAYS)
+                .filter_map(|n| earliest.checked_add_days(Days::new(n)))
+                .filter(|d| matches(rule, *d))
+                .filter_map(at)
+                .find(|ts| *ts > completed_at)
+        }
+    }
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+fn matches(rule: &Recurrence, date: NaiveDate) -> bool {
+    match rule {
+        Recurrence::Daily => true,
+        Recurrence::Weekdays => date.weekday().num_days_from_monday() < 5,
+        Recurrence::Weekly { days } => {
+            days.contains(&(date.weekday().num_days_from_monday() as u8))
+        }
+        Recurrence::Monthly { day } => date.day() == u32::from(*day).min(last_day_of_month(date)),
+        Recurrence::AfterCompletion { .. } => true,
+    }
+}
+
+fn last_day_of_month(date: NaiveDate) -> u32 {
+    let (year, month) = if date.month() == 12 {
+        (date.year() + 1, 1)
+    } else {
+        (date.year(), date.month() + 1)
+    };
+    NaiveDate::from_ymd_opt(year, month, 1)
+        .and_then(|d| d.pred_opt())
+        .map_or(31, |d| d.day())
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, NaiveDateTime};
+
+    use super::*;
+    use crate::database::Settings;
+
+    fn calendar(timezone: &str) -> Calendar {
+        Calendar::from_settings(&Settings { timezone: timezone.into(), ..Settings::default() })
+    }
+
+    /// `local` ("YYYY-MM-DD HH:MM") as an instant in `calendar`'s timezone.
+    fn at(calendar: &Calendar, local: &str) -> i64 {
+        let local = NaiveDateTime::parse_from_str(local, "%Y-%m-%d %H:%M").unwrap();
+        calendar.timestamp(local).unwrap()
+    }
+
+    #[test]
+    fn next_due_follows_the_rule() {
+        let weekly_wed = Recurrence::Weekly { days: vec![2] };
+        // (timezone, rule, due, completed, expected next due), all local
+        let cases: &[(&str, Recurrence, Option<&str>, &str, &str)] = &[
+            ("Europe/Berlin", Recurrence::Daily,    Some("2026-01-10 09:00"), "2026-01-10 12:00", "2026-01-11 09:00"),
+            // Finishing late skips the missed days but keeps the time of day,
+            // and never lands before the completion
+            ("Europe/Berlin", Recurrence::Daily,    Some("2026-01-10 09:00"), "2026-01-15 20:00", "2026-01-16 09:00"),
+            ("Europe/Berlin", Recurrence::Daily,    Some("2026-01-10 09:00"), "2026-01-15 08:00", "2026-01-15 09:00"),
+            ("Europe/Berlin", Recurrence::Weekdays, Some("2026-01-09 09:00"), "2026-01-09 10:00", "2026-01-12 09:00"),
+            ("Europe/Berlin", weekly_wed,           Some("2026-01-12 09:00"), "2026-01-12 10:00", "2026-01-14 09:00"),
+            // Month-end days clamp to shorter months and come back after
+            ("Europe/Berlin", Recurrence::Monthly { day: 31 }, Some("2026-01-31 09:00"), "2026-01-31 10:00", "2026-02-28 09:00"),
+            ("Europe/Berlin", Recurrence::Monthly { day: 31 }, Some("2026-02-28 09:00"), "2026-02-28 10:00", "2026-03-31 09:00"),
+            ("Europe/Berlin", Recurrence::Monthly { day: 30 }, Some("2028-01-30 09:00"), "2028-01-30 10:00", "2028-02-29 09:00"),
+            ("Europe/Berlin", Recurrence::Monthly { day: 15 }, Some("2026-12-15 09:00"), "2026-12-15 10:00", "2027-01-15 09:00"),
+            ("Europe/Berlin", Recurrence::AfterCompletion { days: 3 }, None, "2026-01-10 22:30", "2026-01-13 22:30"),
+            // 02:30 doesn't exist on the spring DST day; it moves on by the hour-long gap
+            ("Europe/Berlin", Recurrence::Daily,    Some("2026-03-28 02:30"), "2026-03-28 03:00", "2026-03-29 03:30"),
+            // Friday evening in UTC is already Saturday in Tokyo
+            ("UTC",           Recurrence::Weekdays, Some("2026-01-09 20:00"), "2026-01-09 21:00", "2026-01-12 20:00"),
+            ("Asia/Tokyo",    Recurrence::Weekdays, Some("2026-01-10 05:00"), "2026-01-10 06:00", "2026-01-12 05:00"),
+        ];
+        for (timezone, rule, due, completed, expected) in cases {
+            let cal = calendar(timezone);
+            let next = next_due(&cal, rule, due.map(|d| at(&cal, d)), at(&cal, completed));
+            assert_eq!(next, Some(at(&cal, expected)), "{timezone} {rule:?} due {due:?}");
+        }
+    }
+
+    #[test]
+    fn next_due_takes_the_earlier_of_a_repeated_hour() {
+        let cal = calendar("Europe/Berlin");
+        let due = at(&cal, "2026-10-24 02:30");
+        let next = next_due(&cal, &Recurrence::Daily, Some(due), due).unwrap();
+        // 02:30 happens twice on 25 October; the first is still summer time
+        assert_eq!(DateTime::from_timestamp(next, 0).unwrap().to_rfc3339(), "2026-10-25T00:30:00+00:00");
+    }
+}
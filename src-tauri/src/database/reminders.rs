@@ -0,0 +1,69 @@
+// database/reminders.rs
+//
+// Due-date reminders. Every reminder shown is written to `reminder_log`,
+// keyed by task, kind ("before:<minutes>" or "overdue") and the due date it
+// was for, so restarts don't repeat it and moving the due date re-arms it.
+
+use chrono::Utc;
+use rusqlite::{Connection, Result};
+
+use super::models::{Reminder, ReminderKind};
+
+/// Record and return the reminders that have become due: for each open task,
+/// the closest of `offsets` (minutes before its due date) that has been
+/// reached, or an overdue notice once the due date has passed. Earlier
+/// offsets that were skipped (e.g. while the app was closed) are recorded
+/// without being returned.
+pub fn take_due(conn: &Connection, offsets: &[u32]) -> Result<Vec<Reminder>> {
+    let now = Utc::now().timestamp();
+    let horizon = offsets.iter().copied().max().map_or(0, |m| i64::from(m) * 60);
+
+    let tx = conn.unchecked_transaction()?;
+    let mut stmt = tx.prepare(
+        "SELECT id, title, due_date FROM tasks
+         WHERE due_date <= ?2 + ?1 AND completed_at IS NULL
+         ORDER BY due_date",
+    )?;
+    let candidates: Vec<(i64, String, i64)> = stmt
+        .query_map((horizon, now), |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .filter_map(Result::ok)
+        .collect();
+    drop(stmt);
+
+    let mut offsets = offsets.to_vec();
+    offsets.sort_unstable();
+    offsets.dedup();
+
+    let mut reminders = Vec::new();
+    for (task_id, title, due_date) in candidates {
+        let kind = if now >= due_date {
+            record(&tx, task_id, "overdue", due_date, now)?.then_some(ReminderKind::Overdue)
+        } else {
+            // Largest first, so the closest reached offset is recorded last
+            let mut newest = None;
+            for &minutes in offsets.iter().rev().filter(|m| due_date - i64::from(**m) * 60 <= now) {
+                if record(&tx, task_id, &format!("before:{minutes}"), due_date, now)? {
+                    newest = Some(ReminderKind::Upcoming { minutes_before: minutes });
+                }
+            }
+            newest
+        };
+        if let Some(kind) = kind {
+            reminders.push(Reminder { task_id, title, due_date, kind });
+        }
+    }
+    tx.commit()?;
+    Ok(reminders)
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+/// Log a reminder; false if it had already been sent.
+fn record(conn: &Connection, task_id: i64, kind: &str, due_date: i64, now: i64) -> Result<bool> {
+    let inserted = conn.execute(
+        "INSERT OR IGNORE INTO reminder_log (task_id, kind, due_date, sent_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        (task_id, kind, due_date, now),
+    )?;
+    Ok(inserted > 0)
+}
@@ -0,0 +1,67 @@
+// database/secrets.rs — Encrypted secret storage (backed by the secrets table)
+//
+// Rows hold ciphertext only; encryption and decryption happen in
+// `crate::vault`, which owns the key. Nothing here ever sees plaintext.
+
+use rusqlite::{Connection, OptionalExtension, Result};
+
+/// A stored secret: its name, the AEAD nonce and the ciphertext.
+pub type SecretRow = (String, Vec<u8>, Vec<u8>);
+
+/// Return the nonce and ciphertext stored under `name`, if any.
+pub fn get(conn: &Connection, name: &str) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+    conn.query_row(
+        "SELECT nonce, ciphertext FROM secrets WHERE name = ?1",
+        [name],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )
+    .optional()
+}
+
+pub fn set(conn: &Connection, name: &str, nonce: &[u8], ciphertext: &[u8]) -> Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO secrets (name, nonce, ciphertext) VALUES (?1, ?2, ?3)",
+        (name, nonce, ciphertext),
+    )?;
+    Ok(())
+}
+
+pub fn delete(conn: &Connection, name: &str) -> Result<()> {
+    conn.execute("DELETE FROM secrets WHERE name = ?1", [name])?;
+    Ok(())
+}
+
+/// Names of every stored secret.
+pub fn names(conn: &Connection) -> Result<Vec<String>> {
+    let mut stmt = conn.prepare("SELECT name FROM secrets ORDER BY name")?;
+    let rows = stmt.query_map([], |row| row.get(0))?.filter_map(Result::ok).collect();
+    Ok(rows)
+}
+
+/// Every stored secret, used when re-encrypting under a new key.
+pub fn all(conn: &Connection) -> Result<Vec<SecretRow>> {
+    let mut stmt = conn.prepare("SELECT name, nonce, ciphertext FROM secrets")?;
+    let rows = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)))?
+        .filter_map(Result::ok)
+        .collect();
+    Ok(rows)
+}
+
+/// Overwrite the given secrets in one transaction.
+pub fn replace_all(conn: &Connection, rows: &[SecretRow]) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    for (name, nonce, ciphertext) in rows {
+        set(&tx, name, nonce, ciphertext)?;
+    }
+    tx.commit()
+}
+
+/// Move a plaintext setting into the secrets table in one transaction,
+/// so the value never exists in both places (or neither).
+pub fn adopt_setting(conn: &Connection, name: &str, nonce: &[u8], ciphertext: &[u8]) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    set(&tx, name, nonce, ciphertext)?;
+    tx.execute("DELETE FROM settings WHERE key = ?1", [name])?;
+    tx.commit()
+}
@@ -5,7 +5,26 @@
 use rusqlite::{Connection, Result};
 use chrono::Utc;
 
-use super::models::{DailyStats, PomodoroRecord, TaskStats};
+use super::models::{BillableSession, DailyStats, PomodoroRecord, TaskStats};
+
+/// Completed work sessions in `[?1, ?2]` with the id of their task (or
+/// NULL), as the `matched` CTE. This is the one rule for linking sessions
+/// to tasks that stats, budgets and billing share: work phases only, and
+/// when several tasks share the session's title the newest one decides.
+pub(super) const MATCHED_SESSIONS: &str = "
+    WITH matched AS (
+        SELECT s.task_name,
+               s.elapsed,
+               s.timestamp,
+               (SELECT t.id FROM tasks t
+                WHERE  t.title = s.task_name
+                ORDER  BY t.id DESC LIMIT 1) AS task_id
+        FROM   pomodoro_sessions s
+        WHERE  s.timestamp >= ?1
+          AND  s.timestamp <= ?2
+          AND  s.action     = 'complete'
+          AND  s.phase      = 0
+    )";
 
 /// Record a timer action (start, pause, resume) — elapsed is 0 for non-complete actions.
 pub fn log_action(conn: &Connection, task_name: &str, action: &str, elapsed: u64, phase: u8) -> Result<()> {
@@ -56,10 +75,12 @@ pub fn insert_record(conn: &Connection, task_name: &str, action: &str, elapsed:
     Ok(())
 }
 
-/// Delete all session records (used by reset_database).
+/// Delete all session records and task switches (used by reset_database).
 pub fn clear_all(conn: &Connection) -> Result<()> {
-    conn.execute("DELETE FROM pomodoro_sessions", [])?;
-    Ok(())
+    conn.execute_batch("
+        DELETE FROM pomodoro_sessions;
+        DELETE FROM task_switches;
+    ")
 }
 
 /// Return the N most recently used distinct task names, ordered by last use.
@@ -108,10 +129,11 @@ pub fn get_task_stats(conn: &Connection, start: i64, end: i64) -> Result<Vec<Tas
     Ok(rows)
 }
 
-/// Same as `get_task_stats` but broken down by calendar day.
+/// Same as `get_task_stats` but broken down by day, in the configured
+/// timezone and with its day rollover (see `calendar`).
 pub fn get_daily_breakdown(conn: &Connection, start: i64, end: i64) -> Result<Vec<DailyStats>> {
     let mut stmt = conn.prepare(
-        "SELECT DATE(timestamp, 'unixepoch') AS day,
+        "SELECT local_day(timestamp) AS day,
                 task_name,
                 COUNT(*)    AS sessions,
                 SUM(elapsed) AS total_seconds
@@ -137,6 +159,31 @@ pub fn get_daily_breakdown(conn: &Connection, start: i64, end: i64) -> Result<Ve
     Ok(rows)
 }
 
+/// Completed work sessions in a time window, oldest first, with the project
+/// of their task as matched by `MATCHED_SESSIONS`.
+pub fn get_billable(conn: &Connection, start: i64, end: i64) -> Result<Vec<BillableSession>> {
+    let mut stmt = conn.prepare(&format!(
+        "{MATCHED_SESSIONS}
+         SELECT m.task_name, t.project_id, m.elapsed, m.timestamp
+         FROM   matched m
+         LEFT   JOIN tasks t ON t.id = m.task_id
+         WHERE  TRIM(m.task_name) != ''
+         ORDER  BY m.timestamp"
+    ))?;
+    let rows = stmt
+        .query_map([start, end], |row| {
+            Ok(BillableSession {
+                task_name:  row.get(0)?,
+                project_id: row.get(1)?,
+                elapsed:    row.get(2)?,
+                timestamp:  row.get(3)?,
+            })
+        })?
+        .filter_map(Result::ok)
+        .collect();
+    Ok(rows)
+}
+
 // ── Shared helper ─────────────────────────────────────────────────────────
 
 fn query_records(stmt: &mut rusqlite::Statement<'_>, params: impl rusqlite::Params) -> Result<Vec<PomodoroRecord>> {
@@ -1,6 +1,15 @@
 // database/settings.rs — Persistent key-value store (backed by the settings table)
+//
+// The raw store is plain `key → string` rows. On top of it sits the typed
+// `Settings` model: each field is one row named after the field, and a
+// row's string is parsed according to the field's type (taken from its
+// default value). Rows that are missing or no longer parse fall back to
+// the default, so adding a field never needs a migration.
 
 use rusqlite::{Connection, Result};
+use serde_json::{Map, Value};
+
+use super::models::Settings;
 
 /// Return a setting value, or `None` if the key doesn't exist yet.
 pub fn get(conn: &Connection, key: &str) -> Result<Option<String>> {
@@ -19,3 +28,153 @@ pub fn set(conn: &Connection, key: &str, value: &str) -> Result<()> {
     )?;
     Ok(())
 }
+
+/// Remove a setting. A missing key is not an error.
+pub fn delete(conn: &Connection, key: &str) -> Result<()> {
+    conn.execute("DELETE FROM settings WHERE key = ?1", [key])?;
+    Ok(())
+}
+
+// ── Typed settings ────────────────────────────────────────────────────────
+
+/// Read every typed setting, using defaults for missing or unparsable rows.
+pub fn load(conn: &Connection) -> Result<Settings> {
+    let mut fields = Settings::default().to_map();
+    for (key, value) in fields.iter_mut() {
+        if let Some(parsed) = get(conn, key)?.and_then(|raw| parse_raw(value, &raw)) {
+            *value = parsed;
+        }
+    }
+    Ok(serde_json::from_value(Value::Object(fields)).unwrap_or_default())
+}
+
+/// Merge `changes` into the stored settings and write the changed ones,
+/// reading and writing in one transaction so concurrent updates can't drop
+/// each other's changes. A validation error is returned without saving.
+/// Untouched settings keep their rows (or lack of one), so an unset value
+/// isn't stored as its default.
+pub fn update(
+    conn:    &Connection,
+    changes: Map<String, Value>,
+    strict:  bool,
+) -> Result<std::result::Result<Settings, String>> {
+    let tx = conn.unchecked_transaction()?;
+    let keys: Vec<String> = changes.keys().cloned().collect();
+    let settings = match load(&tx)?.merged(changes, strict) {
+        Ok(settings) => settings,
+        Err(e) => return Ok(Err(e)),
+    };
+    let fields = settings.to_map();
+    for key in keys {
+        // Unknown names are only left when not `strict`; they're skipped
+        if let Some(value) = fields.get(&key) {
+            set(&tx, &key, &to_raw(value))?;
+        }
+    }
+    tx.commit()?;
+    Ok(Ok(settings))
+}
+
+impl Settings {
+    /// The settings as a JSON object keyed by setting name.
+    pub fn to_map(&self) -> Map<String, Value> {
+        match serde_json::to_value(self) {
+            Ok(Value::Object(map)) => map,
+            _ => Map::new(),
+        }
+    }
+
+    /// Parse the stored string form of `key` into its typed JSON value.
+    pub fn parse(key: &str, raw: &str) -> std::result::Result<Value, String> {
+        let defaults = Settings::default().to_map();
+        let like = defaults.get(key).ok_or_else(|| format!("Unknown setting: {key}"))?;
+        parse_raw(like, raw).ok_or_else(|| format!("Invalid value for {key}: {raw}"))
+    }
+
+    /// Apply `changes` (setting name → JSON value) on top of these settings
+    /// and validate the result. Unknown names are an error when `strict`,
+    /// and skipped otherwise (e.g. a profile from a newer version).
+    pub fn merged(&self, changes: Map<String, Value>, strict: bool) -> std::result::Result<Settings, String> {
+        let mut fields = self.to_map();
+        for (key, value) in changes {
+            match fields.get_mut(&key) {
+                Some(slot) => *slot = value,
+                None if strict => return Err(format!("Unknown setting: {key}")),
+                None => {}
+            }
+        }
+        let settings: Settings = serde_json::from_value(Value::Object(fields))
+            .map_err(|e| format!("Invalid settings: {e}"))?;
+        settings.validate()?;
+        Ok(settings)
+    }
+
+    /// Check every field's constraints.
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        let timezone = self.timezone.trim();
+        if !timezone.is_empty() && timezone.parse::<chrono_tz::Tz>().is_err() {
+            return Err(format!("Unknown timezone: {timezone}."));
+        }
+        if self.day_rollover_hour > 23 {
+            return Err("day_rollover_hour must be between 0 and 23.".into());
+        }
+        if self.week_start > 6 {
+            return Err("week_start must be between 0 (Monday) and 6 (Sunday).".into());
+        }
+
+        if self.due_reminder_offsets.iter().any(|m| !(1..=43_200).contains(m)) {
+            return Err("due_reminder_offsets must be between 1 minute and 30 days.".into());
+        }
+
+        if self.budget_alert_thresholds.iter().any(|t| !(1..=1000).contains(t)) {
+            return Err("budget_alert_thresholds must be percentages between 1 and 1000.".into());
+        }
+
+        let repo = self.github_repo.trim();
+        if !repo.is_empty() {
+            let parts: Vec<&str> = repo.split('/').collect();
+            if parts.len() != 2 || parts.iter().any(|p| p.is_empty() || p.contains(char::is_whitespace)) {
+                return Err("github_repo must look like \"owner/repo\".".into());
+            }
+        }
+
+        let host = self.gitlab_host.trim();
+        if !host.is_empty() && !host.starts_with("https://") && !host.starts_with("http://") {
+            return Err("gitlab_host must start with http:// or https://.".into());
+        }
+
+        if self.jira_domain.trim().contains(char::is_whitespace) {
+            return Err("jira_domain must not contain spaces.".into());
+        }
+
+        let email = self.jira_email.trim();
+        if !email.is_empty() && !email.contains('@') {
+            return Err("jira_email must be an email address.".into());
+        }
+
+        Ok(())
+    }
+}
+
+/// Convert a stored string to the JSON type of `like` (the field's default).
+fn parse_raw(like: &Value, raw: &str) -> Option<Value> {
+    match like {
+        // As the frontend has always read them: anything but "false" is on
+        Value::Bool(_) => Some(Value::Bool(raw != "false")),
+        Value::Number(_) => raw
+            .parse::<i64>()
+            .map(Value::from)
+            .ok()
+            .or_else(|| raw.parse::<f64>().ok().and_then(serde_json::Number::from_f64).map(Value::Number)),
+        Value::String(_) => Some(Value::String(raw.to_string())),
+        Value::Array(_) => serde_json::from_str::<Value>(raw).ok().filter(Value::is_array),
+        _ => None,
+    }
+}
+
+fn to_raw(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,319 @@
+// database/stats.rs
+//
+// Session statistics beyond the per-task totals in `sessions`: totals by
+// project, tag, integration source or status, weekly / monthly trends and
+// the weekday × hour focus heatmap.
+//
+// Sessions only store the task's title, so they are linked to tasks by
+// title; when several tasks share one, the newest decides. Work that can't
+// be attributed (no task, or a task without a project, tag, …) is reported
+// as an explicit "Unassigned" bucket with `unassigned` set.
+
+// Trends cover every week or month touching the requested range, in full,
+// bucketed by the `local_week` / `local_month` SQL functions (see
+// `calendar`). The periods are passed to SQL as a JSON array so that weeks
+// or months without any work still get a row.
+//
+// The heatmap is built in Rust instead: a session's `timestamp` is when it
+// ended, so it covers `[timestamp - elapsed, timestamp]`, and is split at
+// local hour boundaries.
+
+use std::collections::HashMap;
+
+use chrono::{Duration, Months, NaiveDate};
+use rusqlite::{Connection, Result};
+
+use super::calendar::Calendar;
+use super::models::{FocusHeatmap, GroupStats, PeriodTrend, SeriesBy, SeriesLine, StatsGroup, TrendPeriod, TrendSeries};
+use super::sessions::MATCHED_SESSIONS;
+
+/// Name of the bucket for work that can't be attributed.
+const UNASSIGNED: &str = "Unassigned";
+
+/// Work time per group, largest first. Breaks aren't counted. A session
+/// whose task has several tags counts towards each of them.
+pub fn grouped(conn: &Connection, group: StatsGroup, start: i64, end: i64) -> Result<Vec<GroupStats>> {
+    let (columns, joins) = match group {
+        StatsGroup::Project => (
+            "p.id AS id, p.name AS name",
+            "LEFT JOIN tasks t    ON t.id = m.task_id
+             LEFT JOIN projects p ON p.id = t.project_id",
+        ),
+        StatsGroup::Tag => (
+            "g.id AS id, g.name AS name",
+            "LEFT JOIN task_tags tt ON tt.task_id = m.task_id
+             LEFT JOIN tags g       ON g.id = tt.tag_id",
+        ),
+        StatsGroup::Source => (
+            "NULL AS id, t.source AS name",
+            "LEFT JOIN tasks t ON t.id = m.task_id",
+        ),
+        StatsGroup::Status => (
+            "NULL AS id, t.status AS name",
+            "LEFT JOIN tasks t ON t.id = m.task_id",
+        ),
+    };
+    let mut stmt = conn.prepare(&format!(
+        "{MATCHED_SESSIONS},
+         groups AS (
+             SELECT {columns}, m.elapsed
+             FROM   matched m
+             {joins}
+         )
+         SELECT id,
+                COALESCE(name, ?3) AS name,
+                name IS NULL       AS unassigned,
+                COUNT(*)           AS sessions,
+                SUM(elapsed)       AS total_seconds
+         FROM   groups
+         GROUP  BY id, name
+         ORDER  BY total_seconds DESC"
+    ))?;
+    let rows = stmt
+        .query_map((start, end, UNASSIGNED), |row| {
+            Ok(GroupStats {
+                id:            row.get(0)?,
+                name:          row.get(1)?,
+                unassigned:    row.get(2)?,
+                sessions:      row.get(3)?,
+                total_seconds: row.get(4)?,
+            })
+        })?
+        .filter_map(Result::ok)
+        .collect();
+    Ok(rows)
+}
+
+/// Work totals per period, each compared with the period before it and the
+/// same period a year earlier.
+pub fn trends(
+    conn:     &Connection,
+    calendar: &Calendar,
+    period:   TrendPeriod,
+    start:    i64,
+    end:      i64,
+) -> Result<Vec<PeriodTrend>> {
+    let days = periods(calendar, period, start, end);
+    let (Some(first), Some(last)) = (days.first(), days.last()) else { return Ok(vec![]) };
+    let bucket = bucket_fn(period);
+    let (previous, last_year) = match period {
+        TrendPeriod::Week => (
+            "date(p.period, '-7 days')",
+            "date(p.period, '-364 days')",
+        ),
+        TrendPeriod::Month => (
+            "strftime('%Y-%m', p.period || '-01', '-1 month')",
+            "strftime('%Y-%m', p.period || '-01', '-1 year')",
+        ),
+    };
+    // A year and a period before the first one, for the comparisons.
+    let from = calendar.day_start(*first - Duration::days(400));
+    let to = calendar.day_start(next(period, *last)) - 1;
+
+    let mut stmt = conn.prepare(&format!(
+        "WITH buckets AS (
+             SELECT {bucket}(timestamp)                  AS period,
+                    COUNT(*)                             AS sessions,
+                    SUM(elapsed)                         AS total_seconds,
+                    COUNT(DISTINCT local_day(timestamp)) AS working_days
+             FROM   pomodoro_sessions
+             WHERE  timestamp >= ?1
+               AND  timestamp <= ?2
+               AND  action     = 'complete'
+               AND  phase      = 0
+             GROUP  BY period
+         ),
+         periods AS (SELECT value AS period FROM json_each(?3))
+         SELECT p.period,
+                COALESCE(b.sessions, 0),
+                COALESCE(b.total_seconds, 0),
+                COALESCE(b.working_days, 0),
+                COALESCE(b.total_seconds / b.working_days, 0),
+                COALESCE(prev.total_seconds, 0),
+                COALESCE(ly.total_seconds, 0),
+                COALESCE(b.total_seconds, 0) - COALESCE(prev.total_seconds, 0),
+                COALESCE(b.total_seconds, 0) - COALESCE(ly.total_seconds, 0)
+         FROM   periods p
+         LEFT   JOIN buckets b    ON b.period    = p.period
+         LEFT   JOIN buckets prev ON prev.period = {previous}
+         LEFT   JOIN buckets ly   ON ly.period   = {last_year}
+         ORDER  BY p.period"
+    ))?;
+    let rows = stmt
+        .query_map((from, to, to_json(&keys(period, &days))), |row| {
+            Ok(PeriodTrend {
+                period:                      row.get(0)?,
+                sessions:                    row.get(1)?,
+                total_seconds:               row.get(2)?,
+                working_days:                row.get(3)?,
+                avg_seconds_per_working_day: row.get(4)?,
+                previous_seconds:            row.get(5)?,
+                last_year_seconds:           row.get(6)?,
+                delta_previous:              row.get(7)?,
+                delta_last_year:             row.get(8)?,
+            })
+        })?
+        .filter_map(Result::ok)
+        .collect();
+    Ok(rows)
+}
+
+/// Work time per period for the `limit` tasks or projects with the most
+/// work in the range. Sessions without a task title (or whose task has no
+/// project) form the "Unassigned" line, which has `unassigned` set.
+pub fn series(
+    conn:     &Connection,
+    calendar: &Calendar,
+    period:   TrendPeriod,
+    by:       SeriesBy,
+    start:    i64,
+    end:      i64,
+    limit:    usize,
+) -> Result<TrendSeries> {
+    let days = periods(calendar, period, start, end);
+    let keys = keys(period, &days);
+    let (Some(first), Some(last)) = (days.first(), days.last()) else {
+        return Ok(TrendSeries { periods: keys, lines: vec![] });
+    };
+    let bucket = bucket_fn(period);
+    let (columns, joins) = match by {
+        SeriesBy::Task => ("m.task_id AS id, NULLIF(TRIM(m.task_name), '') AS name", ""),
+        SeriesBy::Project => (
+            "p.id AS id, p.name AS name",
+            "LEFT JOIN tasks t    ON t.id = m.task_id
+             LEFT JOIN projects p ON p.id = t.project_id",
+        ),
+    };
+    let from = calendar.day_start(*first);
+    let to = calendar.day_start(next(period, *last)) - 1;
+
+    // Rows come grouped by line, the line with the most work first.
+    let mut stmt = conn.prepare(&format!(
+        "{MATCHED_SESSIONS},
+         totals AS (
+             SELECT {columns},
+                    {bucket}(m.timestamp) AS period,
+                    SUM(m.elapsed)        AS seconds
+             FROM   matched m
+             {joins}
+             GROUP  BY 1, 2, 3
+         )
+         SELECT id, name, period, seconds
+         FROM   totals
+         WHERE  period IN (SELECT value FROM json_each(?3))
+         ORDER  BY SUM(seconds) OVER (PARTITION BY id, name) DESC, id, name"
+    ))?;
+    let rows = stmt.query_map((from, to, to_json(&keys)), |row| {
+        Ok((
+            row.get::<_, Option<i64>>(0)?,
+            row.get::<_, Option<String>>(1)?,
+            row.get::<_, String>(2)?,
+            row.get::<_, i64>(3)?,
+        ))
+    })?;
+
+    let index: HashMap<&str, usize> = keys.iter().enumerate().map(|(i, k)| (k.as_str(), i)).collect();
+    let mut lines: Vec<SeriesLine> = Vec::new();
+    for (id, name, period, seconds) in rows.filter_map(Result::ok) {
+        let unassigned = name.is_none();
+        let name = name.unwrap_or_else(|| UNASSIGNED.to_string());
+        let same_line = lines
+            .last()
+            .is_some_and(|l| l.id == id && l.unassigned == unassigned && l.name == name);
+        if !same_line {
+            if lines.len() == limit {
+                break;
+            }
+            lines.push(SeriesLine { id, name, unassigned, seconds: vec![0; keys.len()] });
+        }
+        if let (Some(line), Some(&i)) = (lines.last_mut(), index.get(period.as_str())) {
+            line.seconds[i] = seconds;
+        }
+    }
+    Ok(TrendSeries { periods: keys, lines })
+}
+
+/// Work time in `[start, end]` by weekday and hour. Hours before the day
+/// rollover count towards the previous day's row.
+pub fn heatmap(conn: &Connection, calendar: &Calendar, start: i64, end: i64) -> Result<FocusHeatmap> {
+    let mut stmt = conn.prepare(
+        "SELECT timestamp - elapsed, timestamp
+         FROM   pomodoro_sessions
+         WHERE  timestamp           >= ?1
+           AND  timestamp - elapsed <= ?2
+           AND  action               = 'complete'
+           AND  phase                = 0",
+    )?;
+    let intervals: Vec<(i64, i64)> = stmt
+        .query_map([start, end], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(Result::ok)
+        .collect();
+
+    let mut map = FocusHeatmap::default();
+    for (row, weekday) in (0u32..).zip(map.weekdays.iter_mut()) {
+        *weekday = (calendar.week_start() + row) % 7;
+    }
+    for (from, to) in intervals {
+        let (mut at, to) = (from.max(start), to.min(end));
+        let mut started = false;
+        while at < to {
+            let piece_end = calendar.hour_end(at).min(to);
+            let row = calendar.weekday_index(calendar.day(at)) as usize;
+            let hour = calendar.hour(at) as usize;
+            map.minutes[row][hour] += (piece_end - at) as f64 / 60.0;
+            if !started {
+                map.sessions[row][hour] += 1;
+                started = true;
+            }
+            at = piece_end;
+        }
+    }
+    Ok(map)
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+/// The first day of every period touching `[start, end]`.
+fn periods(calendar: &Calendar, period: TrendPeriod, start: i64, end: i64) -> Vec<NaiveDate> {
+    let (mut day, last) = match period {
+        TrendPeriod::Week  => (calendar.week(start), calendar.week(end)),
+        TrendPeriod::Month => (calendar.month(start), calendar.month(end)),
+    };
+    let mut days = Vec::new();
+    while day <= last {
+        days.push(day);
+        day = next(period, day);
+    }
+    days
+}
+
+fn next(period: TrendPeriod, day: NaiveDate) -> NaiveDate {
+    match period {
+        TrendPeriod::Week  => day + Duration::days(7),
+        TrendPeriod::Month => day.checked_add_months(Months::new(1)).unwrap_or(NaiveDate::MAX),
+    }
+}
+
+/// The calendar SQL function that names a timestamp's period.
+fn bucket_fn(period: TrendPeriod) -> &'static str {
+    match period {
+        TrendPeriod::Week  => "local_week",
+        TrendPeriod::Month => "local_month",
+    }
+}
+
+/// How `local_week` / `local_month` name the period starting on `day`.
+fn key(period: TrendPeriod, day: NaiveDate) -> String {
+    match period {
+        TrendPeriod::Week  => day.format("%Y-%m-%d").to_string(),
+        TrendPeriod::Month => day.format("%Y-%m").to_string(),
+    }
+}
+
+fn keys(period: TrendPeriod, days: &[NaiveDate]) -> Vec<String> {
+    days.iter().map(|day| key(period, *day)).collect()
+}
+
+fn to_json(keys: &[String]) -> String {
+    serde_json::to_string(keys).unwrap_or_else(|_| "[]".into())
+}
@@ -0,0 +1,122 @@
+// database/switches.rs
+//
+// Context switches: a switch is a work session starting on a different task
+// than the previous work session, recorded in `task_switches` as it
+// happens. A switch that cuts a running session short is `mid_session`,
+// and that session also gets a "switch" row in the action log, so focus
+// metrics can tell it from one that ran out.
+
+use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension, Result};
+
+use super::models::{SwitchDay, SwitchPair, SwitchReport};
+use super::sessions;
+
+/// Pairs listed in `SwitchReport::top_pairs`.
+const TOP_PAIRS: i64 = 10;
+
+/// Log a "start" row for a work session on `task_name`, first recording
+/// the switch if the last work session was on another task. `interrupted`
+/// says whether that session was still running.
+pub fn log_work_start(conn: &Connection, task_name: &str, interrupted: bool) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    let previous: Option<String> = tx
+        .query_row(
+            "SELECT task_name FROM pomodoro_sessions
+             WHERE  action = 'start' AND phase = 0
+             ORDER  BY id DESC LIMIT 1",
+            [],
+            |row| row.get(0),
+        )
+        .optional()?;
+
+    let now = Utc::now().timestamp();
+    let from = previous.as_deref().map(str::trim).unwrap_or_default();
+    let to = task_name.trim();
+    if !from.is_empty() && !to.is_empty() && from != to {
+        // Work on `from` since the last work session on any other task.
+        tx.execute(
+            "INSERT INTO task_switches (from_task, to_task, switched_at, mid_session, seconds_on_task)
+             SELECT ?1, ?2, ?3, ?4, COALESCE(SUM(elapsed), 0)
+             FROM   pomodoro_sessions
+             WHERE  id > ?5
+               AND  action = 'complete'
+               AND  phase  = 0
+               AND  TRIM(task_name) = ?1",
+            (from, to, now, interrupted, streak_start(&tx, from)?),
+        )?;
+        if interrupted {
+            sessions::log_action(&tx, from, "switch", 0, 0)?;
+        }
+    }
+    sessions::log_action(&tx, task_name, "start", 0, 0)?;
+    tx.commit()
+}
+
+/// Switches in `[start, end]`, per day and overall.
+pub fn report(conn: &Connection, start: i64, end: i64) -> Result<SwitchReport> {
+    let mut stmt = conn.prepare(
+        "SELECT local_day(switched_at) AS day,
+                COUNT(*),
+                SUM(mid_session),
+                SUM(seconds_on_task)
+         FROM   task_switches
+         WHERE  switched_at >= ?1 AND switched_at <= ?2
+         GROUP  BY day
+         ORDER  BY day",
+    )?;
+    let rows: Vec<(SwitchDay, i64)> = stmt
+        .query_map([start, end], |row| {
+            let day = SwitchDay { day: row.get(0)?, switches: row.get(1)?, mid_session: row.get(2)? };
+            Ok((day, row.get(3)?))
+        })?
+        .filter_map(Result::ok)
+        .collect();
+
+    let switches: i64 = rows.iter().map(|(d, _)| d.switches).sum();
+    let mid_session: i64 = rows.iter().map(|(d, _)| d.mid_session).sum();
+    let seconds: i64 = rows.iter().map(|(_, s)| s).sum();
+
+    let mut stmt = conn.prepare(
+        "SELECT from_task, to_task, COUNT(*) AS count
+         FROM   task_switches
+         WHERE  switched_at >= ?1 AND switched_at <= ?2
+         GROUP  BY from_task, to_task
+         ORDER  BY count DESC, MAX(switched_at) DESC
+         LIMIT  ?3",
+    )?;
+    let top_pairs = stmt
+        .query_map((start, end, TOP_PAIRS), |row| {
+            Ok(SwitchPair { from_task: row.get(0)?, to_task: row.get(1)?, count: row.get(2)? })
+        })?
+        .filter_map(Result::ok)
+        .collect();
+
+    Ok(SwitchReport {
+        switches,
+        mid_session,
+        avg_seconds_before_switch: if switches == 0 { 0 } else { seconds / switches },
+        days: rows.into_iter().map(|(day, _)| day).collect(),
+        top_pairs,
+    })
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+/// Id of the last work start on a task other than `task`, or 0. Walks back
+/// from the newest row and stops there, so it only reads the current streak
+/// instead of scanning the whole log.
+fn streak_start(conn: &Connection, task: &str) -> Result<i64> {
+    let mut stmt = conn.prepare_cached(
+        "SELECT id, task_name FROM pomodoro_sessions
+         WHERE  action = 'start' AND phase = 0
+         ORDER  BY id DESC",
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        if row.get::<_, String>(1)?.trim() != task {
+            return row.get(0);
+        }
+    }
+    Ok(0)
+}
@@ -1,24 +1,36 @@
 // database/tags.rs — Tag CRUD
 //
-// Tags use INSERT OR IGNORE so the same name can't be created twice.
-// Deletion cascades to task_tags via ON DELETE CASCADE.
+// Tag names are unique regardless of case ("Bug" and "bug" are the same
+// tag), enforced by a NOCASE unique index. Deletion cascades to task_tags
+// via ON DELETE CASCADE, which `merge` relies on too.
 
-use rusqlite::{Connection, Result};
+use rusqlite::{Connection, OptionalExtension, Result};
 use super::models::Tag;
 
-/// Create a tag (or silently skip if the name already exists) and return its id.
+/// Create a tag and return its id. If a tag with the same name (ignoring
+/// case) already exists, its id is returned and it is left unchanged.
 pub fn create(conn: &Connection, name: &str, color: Option<&str>) -> Result<i64> {
-    conn.execute(
-        "INSERT OR IGNORE INTO tags (name, color) VALUES (?1, ?2)",
-        (name, color),
-    )?;
-    // IGNORE means last_insert_rowid() is 0 on duplicate; fetch by name instead
-    conn.prepare("SELECT id FROM tags WHERE name = ?1")?
-        .query_row([name], |row| row.get(0))
+    let name = name.trim();
+    if let Some(id) = find(conn, name)? {
+        return Ok(id);
+    }
+    conn.execute("INSERT INTO tags (name, color) VALUES (?1, ?2)", (name, color))?;
+    Ok(conn.last_insert_rowid())
+}
+
+/// Return the id of the tag with this name (ignoring case), creating it if
+/// needed. Used when importing labels.
+pub fn find_or_create(conn: &Connection, name: &str) -> Result<i64> {
+    let name = name.trim();
+    if let Some(id) = find(conn, name)? {
+        return Ok(id);
+    }
+    conn.execute("INSERT INTO tags (name) VALUES (?1)", [name])?;
+    Ok(conn.last_insert_rowid())
 }
 
 pub fn list(conn: &Connection) -> Result<Vec<Tag>> {
-    let mut stmt = conn.prepare("SELECT id, name, color FROM tags ORDER BY name")?;
+    let mut stmt = conn.prepare("SELECT id, name, color FROM tags ORDER BY name COLLATE NOCASE")?;
     let rows = stmt
         .query_map([], |row| {
             Ok(Tag { id: row.get(0)?, name: row.get(1)?, color: row.get(2)? })
@@ -28,7 +40,79 @@ pub fn list(conn: &Connection) -> Result<Vec<Tag>> {
     Ok(rows)
 }
 
+/// Rename and/or recolour a tag. Returns `Ok(false)` if another tag already
+/// has the new name (ignoring case); changing only the case of a tag's own
+/// name is fine. Fails with `QueryReturnedNoRows` if the tag is missing.
+pub fn update(conn: &Connection, tag: &Tag) -> Result<bool> {
+    let name = tag.name.trim();
+    if find(conn, name)?.is_some_and(|id| id != tag.id) {
+        return Ok(false);
+    }
+    let changed = conn.execute(
+        "UPDATE tags SET name = ?1, color = ?2 WHERE id = ?3",
+        (name, &tag.color, tag.id),
+    )?;
+    if changed == 0 {
+        return Err(rusqlite::Error::QueryReturnedNoRows);
+    }
+    Ok(true)
+}
+
+/// Move every task tagged with one of `source_ids` onto `target_id`, then
+/// delete the source tags, in one transaction. Fails with
+/// `QueryReturnedNoRows`, changing nothing, if any tag is missing.
+pub fn merge(conn: &Connection, source_ids: &[i64], target_id: i64) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    tx.query_row("SELECT 1 FROM tags WHERE id = ?1", [target_id], |_| Ok(()))?;
+    for &source_id in source_ids.iter().filter(|id| **id != target_id) {
+        tx.execute(
+            "INSERT OR IGNORE INTO task_tags (task_id, tag_id)
+             SELECT task_id, ?2 FROM task_tags WHERE tag_id = ?1",
+            (source_id, target_id),
+        )?;
+        tx.execute(
+            "INSERT OR IGNORE INTO task_template_tags (template_id, tag_id)
+             SELECT template_id, ?2 FROM task_template_tags WHERE tag_id = ?1",
+            (source_id, target_id),
+        )?;
+        if tx.execute("DELETE FROM tags WHERE id = ?1", [source_id])? == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+    }
+    tx.commit()
+}
+
 pub fn delete(conn: &Connection, id: i64) -> Result<()> {
     conn.execute("DELETE FROM tags WHERE id = ?1", [id])?;
     Ok(())
 }
+
+/// Fold tags whose names differ only by case into the oldest of them, so
+/// the case-insensitive unique index can be created on existing databases.
+pub(super) fn merge_case_duplicates(conn: &Connection) -> Result<()> {
+    let mut stmt = conn.prepare(
+        "SELECT d.id, (SELECT MIN(k.id) FROM tags k WHERE k.name = d.name COLLATE NOCASE)
+         FROM tags d",
+    )?;
+    let pairs: Vec<(i64, i64)> = stmt
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?
+        .filter_map(Result::ok)
+        .filter(|(id, keep)| id != keep)
+        .collect();
+    drop(stmt);
+    for (id, keep) in pairs {
+        merge(conn, &[id], keep)?;
+    }
+    Ok(())
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+fn find(conn: &Connection, name: &str) -> Result<Option<i64>> {
+    conn.query_row(
+        "SELECT id FROM tags WHERE name = ?1 COLLATE NOCASE",
+        [name],
+        |row| row.get(0),
+    )
+    .optional()
+}
@@ -0,0 +1,123 @@
+// database/task_batch.rs
+//
+// One operation applied to many tasks in a single transaction. Each task
+// runs in its own savepoint, so a task that fails (no longer exists, or the
+// change breaks its project's workflow) is reported in its result and rolled
+// back without undoing the others.
+
+use rusqlite::{Connection, Result};
+
+use super::calendar::Calendar;
+use super::models::{BatchOperation, BatchResult, StatusChange};
+use super::{tasks, workflows};
+
+const NOT_FOUND: &str = "Task not found.";
+
+/// Apply `op` to every task in `ids` (duplicates are applied once) and
+/// return one result per id, in order. A project or tag named by `op` that
+/// doesn't exist fails the whole batch with `QueryReturnedNoRows`.
+pub fn apply(
+    conn:     &Connection,
+    calendar: &Calendar,
+    ids:      &[i64],
+    op:       &BatchOperation,
+) -> Result<Vec<BatchResult>> {
+    let mut tx = conn.unchecked_transaction()?;
+    check_references(&tx, op)?;
+
+    let mut results: Vec<BatchResult> = Vec::with_capacity(ids.len());
+    for &id in ids {
+        if results.iter().any(|r| r.id == id) {
+            continue;
+        }
+        let sp = tx.savepoint()?;
+        let error = match apply_one(&sp, calendar, id, op) {
+            Ok(error) => error,
+            Err(rusqlite::Error::QueryReturnedNoRows) => Some(NOT_FOUND.to_string()),
+            Err(e) => Some(e.to_string()),
+        };
+        if error.is_none() {
+            sp.commit()?;
+        }
+        results.push(BatchResult { id, ok: error.is_none(), error });
+    }
+    tx.commit()?;
+    Ok(results)
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+/// The reason the operation failed for this task, if it did.
+fn apply_one(conn: &Connection, calendar: &Calendar, id: i64, op: &BatchOperation) -> Result<Option<String>> {
+    let changed = match op {
+        BatchOperation::SetStatus { status } => {
+            return Ok(tasks::set_status(conn, calendar, id, status)?.error(status));
+        }
+        BatchOperation::SetProject { project_id } => {
+            // The task keeps its status, which the new workflow must have
+            let status: String =
+                conn.query_row("SELECT status FROM tasks WHERE id = ?1", [id], |row| row.get(0))?;
+            let workflow = workflows::for_project(conn, *project_id)?;
+            let change = workflows::check(conn, &workflow, Some(id), *project_id, &status)?;
+            if change != StatusChange::Allowed {
+                return Ok(change.error(&status));
+            }
+            conn.execute("UPDATE tasks SET project_id = ?1 WHERE id = ?2", (project_id, id))?;
+            workflows::refresh_completed(conn, &workflow, *project_id, Some(id))?;
+            1
+        }
+        BatchOperation::SetPriority { priority } => conn.execute(
+            "UPDATE tasks SET priority = ?1 WHERE id = ?2",
+            (priority, id),
+        )?,
+        BatchOperation::SetDueDate { due_date } => conn.execute(
+            "UPDATE tasks SET due_date = ?1 WHERE id = ?2",
+            (due_date, id),
+        )?,
+        BatchOperation::AddTags { tag_ids } => {
+            if !exists(conn, id)? {
+                return Ok(Some(NOT_FOUND.to_string()));
+            }
+            for tag_id in tag_ids {
+                conn.execute(
+                    "INSERT OR IGNORE INTO task_tags (task_id, tag_id) VALUES (?1, ?2)",
+                    (id, tag_id),
+                )?;
+            }
+            1
+        }
+        BatchOperation::RemoveTags { tag_ids } => {
+            if !exists(conn, id)? {
+                return Ok(Some(NOT_FOUND.to_string()));
+            }
+            for tag_id in tag_ids {
+                conn.execute(
+                    "DELETE FROM task_tags WHERE task_id = ?1 AND tag_id = ?2",
+                    (id, tag_id),
+                )?;
+            }
+            1
+        }
+        BatchOperation::Delete => conn.execute("DELETE FROM tasks WHERE id = ?1", [id])?,
+    };
+    Ok((changed == 0).then(|| NOT_FOUND.to_string()))
+}
+
+fn check_references(conn: &Connection, op: &BatchOperation) -> Result<()> {
+    match op {
+        BatchOperation::SetProject { project_id: Some(project_id) } => {
+            conn.query_row("SELECT 1 FROM projects WHERE id = ?1", [project_id], |_| Ok(()))
+        }
+        BatchOperation::AddTags { tag_ids } => {
+            for tag_id in tag_ids {
+                conn.query_row("SELECT 1 FROM tags WHERE id = ?1", [tag_id], |_| Ok(()))?;
+            }
+            Ok(())
+        }
+        _ => Ok(()),
+    }
+}
+
+fn exists(conn: &Connection, id: i64) -> Result<bool> {
+    conn.query_row("SELECT EXISTS (SELECT 1 FROM tasks WHERE id = ?1)", [id], |row| row.get(0))
+}
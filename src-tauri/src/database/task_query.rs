@@ -0,0 +1,345 @@
+// database/task_query.rs
+//
+// Structured task queries: turns a `TaskQuery` into a single SELECT with
+// bound parameters, then applies keyset (cursor) pagination on top of the
+// requested sort order.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine as _};
+use chrono::Utc;
+use rusqlite::types::Value;
+use rusqlite::{Connection, Result};
+
+use super::models::{SortDirection, TagMatch, Task, TaskPage, TaskQuery, TaskSort};
+use super::tasks::{map_task_rows, TASK_COLUMNS};
+
+/// Run `query` and return one page of matching tasks, or the reason its
+/// cursor was rejected.
+pub fn query(conn: &Connection, query: &TaskQuery) -> Result<std::result::Result<TaskPage, String>> {
+    let after = match query.cursor.as_deref() {
+        Some(cursor) => match decode_cursor(cursor) {
+            Some(after) => Some(after),
+            None => return Ok(Err(format!("invalid cursor: {cursor}"))),
+        },
+        None => None,
+    };
+    page(conn, query, after).map(Ok)
+}
+
+/// One page of `query` following the row `after` (see `Cursor`), ignoring
+/// `query.cursor`.
+pub(super) fn page(conn: &Connection, query: &TaskQuery, after: Option<Cursor>) -> Result<TaskPage> {
+    let mut sql    = format!("SELECT {TASK_COLUMNS} FROM tasks t WHERE 1=1 ");
+    let mut params = Vec::new();
+
+    push_filters(query, &mut sql, &mut params);
+
+    let sort_expr = sort_expr(query.sort);
+    let cmp = match query.direction {
+        SortDirection::Asc  => ">",
+        SortDirection::Desc => "<",
+    };
+
+    if let Some(Cursor { key, created_at, id }) = after {
+        // Ties on the sort key break on created_at DESC, then id DESC,
+        // matching the ORDER BY below.
+        sql.push_str(&format!(
+            "AND ({sort_expr} {cmp} ? OR ({sort_expr} = ? AND (t.created_at, t.id) < (?, ?))) "
+        ));
+        params.push(key.clone());
+        params.push(key);
+        params.push(Value::Integer(created_at));
+        params.push(Value::Integer(id));
+    }
+
+    let dir = match query.direction {
+        SortDirection::Asc  => "ASC",
+        SortDirection::Desc => "DESC",
+    };
+    sql.push_str(&format!("ORDER BY {sort_expr} {dir}, t.created_at DESC, t.id DESC "));
+
+    // Fetch one extra row to learn whether another page follows
+    let limit = query.limit.filter(|l| *l > 0);
+    if let Some(limit) = limit {
+        sql.push_str("LIMIT ?");
+        params.push(Value::Integer(limit + 1));
+    }
+
+    let mut tasks = map_task_rows(conn, &sql, rusqlite::params_from_iter(params))?;
+
+    let next_cursor = match limit {
+        Some(limit) if tasks.len() as i64 > limit => {
+            tasks.truncate(limit as usize);
+            tasks.last().map(|t| encode_cursor(query.sort, t))
+        }
+        _ => None,
+    };
+
+    Ok(TaskPage { tasks, next_cursor })
+}
+
+// ── SQL building ──────────────────────────────────────────────────────────
+
+/// Append one `AND …` clause per active filter, pushing its bound values.
+fn push_filters(query: &TaskQuery, sql: &mut String, params: &mut Vec<Value>) {
+    if !query.statuses.is_empty() {
+        sql.push_str(&format!("AND t.status IN ({}) ", placeholders(query.statuses.len())));
+        params.extend(query.statuses.iter().cloned().map(Value::Text));
+    }
+
+    if !query.project_ids.is_empty() {
+        sql.push_str(&format!("AND t.project_id IN ({}) ", placeholders(query.project_ids.len())));
+        params.extend(query.project_ids.iter().copied().map(Value::Integer));
+    }
+
+    if !query.tag_ids.is_empty() {
+        let list = placeholders(query.tag_ids.len());
+        match query.tag_match {
+            TagMatch::Any => sql.push_str(&format!(
+                "AND EXISTS (SELECT 1 FROM task_tags tt
+                             WHERE tt.task_id = t.id AND tt.tag_id IN ({list})) "
+            )),
+            TagMatch::All => sql.push_str(&format!(
+                "AND (SELECT COUNT(DISTINCT tt.tag_id) FROM task_tags tt
+                      WHERE tt.task_id = t.id AND tt.tag_id IN ({list})) = ? "
+            )),
+        }
+        params.extend(query.tag_ids.iter().copied().map(Value::Integer));
+        if query.tag_match == TagMatch::All {
+            let mut distinct = query.tag_ids.clone();
+            distinct.sort_unstable();
+            distinct.dedup();
+            params.push(Value::Integer(distinct.len() as i64));
+        }
+    }
+
+    if let Some(after) = query.due_after {
+        sql.push_str("AND t.due_date >= ? ");
+        params.push(Value::Integer(after));
+    }
+    if let Some(before) = query.due_before {
+        sql.push_str("AND t.due_date <= ? ");
+        params.push(Value::Integer(before));
+    }
+    if query.overdue {
+        sql.push_str("AND t.due_date IS NOT NULL AND t.due_date < ? AND t.completed_at IS NULL ");
+        params.push(Value::Integer(Utc::now().timestamp()));
+    }
+
+    if let Some(min) = query.priority_min {
+        sql.push_str("AND t.priority >= ? ");
+        params.push(Value::Integer(min as i64));
+    }
+    if let Some(max) = query.priority_max {
+        sql.push_str("AND t.priority <= ? ");
+        params.push(Value::Integer(max as i64));
+    }
+
+    if !query.sources.is_empty() {
+        let include_local = query.sources.iter().any(|s| s == "local");
+        let remote: Vec<&String> = query.sources.iter().filter(|s| *s != "local").collect();
+        let mut parts = Vec::new();
+        if !remote.is_empty() {
+            parts.push(format!("t.source IN ({})", placeholders(remote.len())));
+            params.extend(remote.into_iter().cloned().map(Value::Text));
+        }
+        if include_local {
+            parts.push("t.source IS NULL".to_string());
+        }
+        sql.push_str(&format!("AND ({}) ", parts.join(" OR ")));
+    }
+
+    match query.has_parent {
+        Some(true)  => sql.push_str("AND t.parent_id IS NOT NULL "),
+        Some(false) => sql.push_str("AND t.parent_id IS NULL "),
+        None        => {}
+    }
+
+    if query.actionable {
+        sql.push_str(
+            "AND t.completed_at IS NULL
+             AND NOT EXISTS (SELECT 1 FROM task_dependencies d
+                             JOIN tasks b ON b.id = d.blocked_by
+                             WHERE d.task_id = t.id AND b.completed_at IS NULL) ",
+        );
+    }
+
+    if let Some(text) = query.text.as_deref().map(str::trim).filter(|s| !s.is_empty()) {
+        sql.push_str("AND (t.title LIKE ? ESCAPE '\\' OR t.description LIKE ? ESCAPE '\\') ");
+        let pattern = format!("%{}%", escape_like(text));
+        params.push(Value::Text(pattern.clone()));
+        params.push(Value::Text(pattern));
+    }
+}
+
+/// SQL expression used both for ORDER BY and for the cursor comparison.
+/// Missing due dates sort after every real one.
+fn sort_expr(sort: TaskSort) -> &'static str {
+    match sort {
+        TaskSort::Position  => "t.position",
+        TaskSort::CreatedAt => "t.created_at",
+        TaskSort::DueDate   => "COALESCE(t.due_date, 9223372036854775807)",
+        TaskSort::Priority  => "t.priority",
+        TaskSort::Title     => "t.title",
+    }
+}
+
+fn placeholders(n: usize) -> String {
+    vec!["?"; n].join(", ")
+}
+
+/// Escape LIKE wildcards so user text is matched literally.
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_")
+}
+
+// ── Cursors ───────────────────────────────────────────────────────────────
+
+/// Where a page ends: the last row's sort key, plus its `created_at` and id
+/// to break ties.
+#[derive(Debug, PartialEq)]
+pub(super) struct Cursor {
+    key:        Value,
+    created_at: i64,
+    id:         i64,
+}
+
+/// A cursor is JSON-encoded and then base64'd so the frontend treats it as
+/// opaque.
+fn encode_cursor(sort: TaskSort, last: &Task) -> String {
+    let key = match sort {
+        TaskSort::Position  => serde_json::json!(last.position),
+        TaskSort::CreatedAt => serde_json::json!(last.created_at),
+        TaskSort::DueDate   => serde_json::json!(last.due_date.unwrap_or(i64::MAX)),
+        TaskSort::Priority  => serde_json::json!(last.priority),
+        TaskSort::Title     => serde_json::json!(last.title),
+    };
+    URL_SAFE_NO_PAD.encode(serde_json::json!([key, last.created_at, last.id]).to_string())
+}
+
+/// `None` for anything `encode_cursor` couldn't have produced.
+fn decode_cursor(cursor: &str) -> Option<Cursor> {
+    let bytes = URL_SAFE_NO_PAD.decode(cursor).ok()?;
+    let (key, created_at, id): (serde_json::Value, i64, i64) = serde_json::from_slice(&bytes).ok()?;
+
+    let key = match key {
+        serde_json::Value::Number(n) => Value::Integer(n.as_i64()?),
+        serde_json::Value::String(s) => Value::Text(s),
+        _ => return None,
+    };
+    Some(Cursor { key, created_at, id })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::database::schema;
+
+    /// A migrated in-memory database holding `(title, priority, created_at)`
+    /// rows, all at position 0.
+    fn db(rows: &[(&str, i32, i64)]) -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        schema::migrate(&conn).unwrap();
+        for (title, priority, created_at) in rows {
+            conn.execute(
+                "INSERT INTO tasks (title, priority, status, position, created_at)
+                 VALUES (?1, ?2, 'todo', 0, ?3)",
+                (title, priority, created_at),
+            )
+            .unwrap();
+        }
+        conn
+    }
+
+    /// Titles of every page of `query`, following the cursors to the end.
+    fn pages(conn: &Connection, mut query: TaskQuery) -> Vec<Vec<String>> {
+        let mut pages = Vec::new();
+        loop {
+            let page = super::query(conn, &query).unwrap().unwrap();
+            pages.push(page.tasks.iter().map(|t| t.title.clone()).collect());
+            match page.next_cursor {
+                Some(cursor) => query.cursor = Some(cursor),
+                None => return pages,
+            }
+        }
+    }
+
+    fn task(title: &str, priority: i32, due_date: Option<i64>) -> Task {
+        Task {
+            id: 42, title: title.into(), description: None, due_date, priority,
+            status: "todo".into(), project_id: None, parent_id: None, position: 3,
+            external_id: None, source: None, created_at: 1_700_000_000, completed_at: None,
+            tags: vec![], estimate_minutes: None, recurrence: None, blocked: false,
+        }
+    }
+
+    #[test]
+    fn cursor_round_trips() {
+        // (sort, last row, sort key the cursor carries)
+        let cases = [
+            (TaskSort::Position,  task("a", 2, None),      Value::Integer(3)),
+            (TaskSort::CreatedAt, task("a", 2, None),      Value::Integer(1_700_000_000)),
+            (TaskSort::Priority,  task("a", 2, None),      Value::Integer(2)),
+            (TaskSort::DueDate,   task("a", 2, Some(99)),  Value::Integer(99)),
+            // No due date sorts last, as i64::MAX
+            (TaskSort::DueDate,   task("a", 2, None),      Value::Integer(i64::MAX)),
+            // Titles survive quotes, unicode and URL-unsafe characters
+            (TaskSort::Title,     task("\"ñ\" / ?+&", 2, None), Value::Text("\"ñ\" / ?+&".into())),
+        ];
+        for (sort, last, key) in cases {
+            let cursor = encode_cursor(sort, &last);
+            let expected = Cursor { key, created_at: 1_700_000_000, id: 42 };
+            assert_eq!(decode_cursor(&cursor), Some(expected), "{sort:?}");
+        }
+    }
+
+    #[test]
+    fn bad_cursors_are_rejected() {
+        let encode = |json: &str| URL_SAFE_NO_PAD.encode(json);
+        let cursors = [
+            "".to_string(),
+            "not base64!".to_string(),
+            encode("not json"),
+            // The old two-element form, and keys no sort produces
+            encode("[3, 42]"),
+            encode("[null, 1700000000, 42]"),
+            encode("[1.5, 1700000000, 42]"),
+            encode("[[1], 1700000000, 42]"),
+        ];
+        for cursor in &cursors {
+            assert_eq!(decode_cursor(cursor), None, "{cursor:?}");
+        }
+
+        let conn = db(&[]);
+        let query = TaskQuery { cursor: Some("bogus".into()), ..TaskQuery::default() };
+        assert_eq!(super::query(&conn, &query).unwrap().unwrap_err(), "invalid cursor: bogus");
+    }
+
+    #[test]
+    fn pages_are_stable_when_sort_keys_tie() {
+        // Every row shares its priority with another, and "c" / "d" also
+        // share created_at, so only the id tells them apart.
+        let conn = db(&[
+            ("a", 1, 100),
+            ("b", 2, 300),
+            ("c", 1, 200),
+            ("d", 1, 200),
+            ("e", 2, 100),
+        ]);
+        // (sort, direction, page size, expected pages)
+        let cases: &[(TaskSort, SortDirection, i64, &[&[&str]])] = &[
+            // Ties break on created_at DESC, then id DESC
+            (TaskSort::Priority, SortDirection::Asc,  2, &[&["d", "c"], &["a", "b"], &["e"]]),
+            (TaskSort::Priority, SortDirection::Desc, 2, &[&["b", "e"], &["d", "c"], &["a"]]),
+            (TaskSort::Position, SortDirection::Asc,  1, &[&["b"], &["d"], &["c"], &["e"], &["a"]]),
+            (TaskSort::Position, SortDirection::Asc,  5, &[&["b", "d", "c", "e", "a"]]),
+        ];
+        for (sort, direction, limit, expected) in cases {
+            let query = TaskQuery { sort: *sort, direction: *direction, limit: Some(*limit), ..TaskQuery::default() };
+            assert_eq!(pages(&conn, query), *expected, "{sort:?} {direction:?} by {limit}");
+        }
+
+        // Paging doesn't change the order of the full list
+        let all = TaskQuery { sort: TaskSort::Priority, ..TaskQuery::default() };
+        assert_eq!(pages(&conn, all), [["d", "c", "a", "b", "e"]]);
+    }
+}
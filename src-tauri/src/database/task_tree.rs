@@ -0,0 +1,241 @@
+// database/task_tree.rs
+//
+// Subtask hierarchies over `tasks.parent_id`: loading a task with all of its
+// descendants, moving a subtree under a new parent, and completing a parent
+// together with its open subtasks.
+//
+// Descendants are found with a recursive CTE; roll-ups (tracked time,
+// estimates, progress) are computed in Rust once the subtree is loaded.
+// Tracked time is linked through `sessions::MATCHED_SESSIONS`, like the rest
+// of the stats code: a session counts only for the newest task with its
+// title, so recurring copies and same-named subtasks aren't counted twice.
+
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
+
+use chrono::Utc;
+use rusqlite::{Connection, Result};
+
+use super::calendar::Calendar;
+use super::models::{Task, TaskCompletion, TaskNode, TaskRollup};
+use super::sessions::MATCHED_SESSIONS;
+use super::{recurrence, workflows};
+use super::tasks::{map_task_rows, TASK_COLUMNS};
+
+/// Depth limit for loading subtrees. Re-parenting refuses cycles, but rows
+/// written by older versions could still contain one. The cycle check
+/// itself walks ancestors without a limit, so it can't be fooled by depth.
+const MAX_DEPTH: i64 = 64;
+
+/// `subtree(id, depth)`: the task bound to ?1 (depth 0) and all descendants.
+fn subtree_cte() -> String {
+    format!(
+        "WITH RECURSIVE subtree(id, depth) AS (
+             SELECT ?1, 0
+             UNION
+             SELECT t.id, s.depth + 1
+             FROM tasks t JOIN subtree s ON t.parent_id = s.id
+             WHERE s.depth < {MAX_DEPTH}
+         ) "
+    )
+}
+
+/// Load the tree under `root`, or every top-level tree when `root` is
+/// `None`. Tasks whose parent no longer exists count as top-level.
+pub fn load(conn: &Connection, root: Option<i64>) -> Result<Vec<TaskNode>> {
+    let tasks = match root {
+        Some(id) => descendants(conn, id, true)?,
+        None => {
+            let sql = format!(
+                "WITH RECURSIVE subtree(id, depth) AS (
+                     SELECT id, 0 FROM tasks
+                     WHERE parent_id IS NULL
+                        OR parent_id NOT IN (SELECT id FROM tasks)
+                     UNION
+                     SELECT t.id, s.depth + 1
+                     FROM tasks t JOIN subtree s ON t.parent_id = s.id
+                     WHERE s.depth < {MAX_DEPTH}
+                 )
+                 SELECT {TASK_COLUMNS} FROM subtree s JOIN tasks t ON t.id = s.id
+                 ORDER BY s.depth, t.position, t.id DESC"
+            );
+            dedup(map_task_rows(conn, &sql, [])?)
+        }
+    };
+
+    let ids: Vec<i64> = tasks.iter().map(|t| t.id).collect();
+    let tracked = tracked_seconds(conn, &ids)?;
+
+    // Roots are the rows without a parent in the loaded set
+    let ids: HashSet<i64> = tasks.iter().map(|t| t.id).collect();
+    let mut roots = Vec::new();
+    let mut by_parent: HashMap<i64, Vec<Task>> = HashMap::new();
+    for task in tasks {
+        match task.parent_id {
+            Some(parent) if ids.contains(&parent) && Some(task.id) != root => {
+                by_parent.entry(parent).or_default().push(task)
+            }
+            _ => roots.push(task),
+        }
+    }
+
+    Ok(roots
+        .into_iter()
+        .map(|task| build(task, &mut by_parent, &tracked))
+        .collect())
+}
+
+/// Move `id` (and its subtree) under `new_parent`, or to the top level.
+/// Returns `Ok(false)` without changing anything when `new_parent` is the
+/// task itself or one of its descendants. A missing task or parent fails
+/// with `QueryReturnedNoRows`.
+pub fn reparent(conn: &Connection, id: i64, new_parent: Option<i64>) -> Result<bool> {
+    let tx = conn.unchecked_transaction()?;
+    tx.query_row("SELECT 1 FROM tasks WHERE id = ?1", [id], |_| Ok(()))?;
+
+    if let Some(parent) = new_parent {
+        tx.query_row("SELECT 1 FROM tasks WHERE id = ?1", [parent], |_| Ok(()))?;
+        // UNION drops ids already seen, so a stored cycle still terminates
+        let cycle: bool = tx.query_row(
+            "WITH RECURSIVE ancestors(id) AS (
+                 SELECT ?2
+                 UNION
+                 SELECT t.parent_id FROM tasks t JOIN ancestors a ON t.id = a.id
+                 WHERE t.parent_id IS NOT NULL
+             )
+             SELECT EXISTS (SELECT 1 FROM ancestors WHERE id = ?1)",
+            (id, parent),
+            |row| row.get(0),
+        )?;
+        if cycle {
+            return Ok(false);
+        }
+    }
+
+    tx.execute("UPDATE tasks SET parent_id = ?1 WHERE id = ?2", (new_parent, id))?;
+    tx.commit()?;
+    Ok(true)
+}
+
+/// Mark `id` done. With open subtasks, either complete them in the same
+/// transaction (`include_subtasks`) or leave everything untouched and
+/// report them so the caller can warn. Each task moves to the first done
+/// status of its project's workflow; completing is explicit, so transition
+/// rules and WIP limits don't apply. A recurring task spawns its next
+/// occurrence.
+pub fn complete(
+    conn:             &Connection,
+    calendar:         &Calendar,
+    id:               i64,
+    include_subtasks: bool,
+) -> Result<TaskCompletion> {
+    let tx = conn.unchecked_transaction()?;
+    let was_done: bool = tx.query_row(
+        "SELECT completed_at IS NOT NULL FROM tasks WHERE id = ?1",
+        [id],
+        |row| row.get(0),
+    )?;
+
+    let (open_subtasks, open_root): (Vec<Task>, Vec<Task>) = descendants(&tx, id, true)?
+        .into_iter()
+        .filter(|t| t.completed_at.is_none())
+        .partition(|t| t.id != id);
+    if !open_subtasks.is_empty() && !include_subtasks {
+        return Ok(TaskCompletion { completed: false, open_subtasks });
+    }
+
+    let now = Utc::now().timestamp();
+    let mut done_status: HashMap<Option<i64>, String> = HashMap::new();
+    for task in open_root.iter().chain(&open_subtasks) {
+        let status = match done_status.entry(task.project_id) {
+            Entry::Occupied(e) => e.into_mut(),
+            Entry::Vacant(e) => {
+                let workflow = workflows::for_project(&tx, task.project_id)?;
+                e.insert(workflow.done_status().to_string())
+            }
+        };
+        tx.execute(
+            "UPDATE tasks SET status = ?1, completed_at = ?2 WHERE id = ?3",
+            (&*status, now, task.id),
+        )?;
+    }
+
+    // The next occurrence copies the whole subtree, so only the root spawns
+    if !was_done {
+        recurrence::spawn_next(&tx, calendar, id)?;
+    }
+    tx.commit()?;
+    Ok(TaskCompletion { completed: true, open_subtasks })
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+/// The subtree under `id`, shallowest first, optionally including `id` itself.
+pub(super) fn descendants(conn: &Connection, id: i64, include_root: bool) -> Result<Vec<Task>> {
+    let min_depth = if include_root { 0 } else { 1 };
+    let sql = format!(
+        "{} SELECT {TASK_COLUMNS} FROM subtree s JOIN tasks t ON t.id = s.id
+            WHERE s.depth >= {min_depth}
+            ORDER BY s.depth, t.position, t.id DESC",
+        subtree_cte()
+    );
+    Ok(dedup(map_task_rows(conn, &sql, [id])?))
+}
+
+/// Drop repeated rows (only possible when stored data has a cycle),
+/// keeping the shallowest occurrence.
+fn dedup(tasks: Vec<Task>) -> Vec<Task> {
+    let mut seen = HashSet::new();
+    tasks.into_iter().filter(|t| seen.insert(t.id)).collect()
+}
+
+/// Seconds of completed work sessions per task id, for the given tasks.
+fn tracked_seconds(conn: &Connection, ids: &[i64]) -> Result<HashMap<i64, i64>> {
+    const CHUNK: usize = 500;
+
+    let mut out = HashMap::new();
+    for chunk in ids.chunks(CHUNK) {
+        let sql = format!(
+            "{MATCHED_SESSIONS}
+             SELECT task_id, SUM(elapsed) FROM matched
+             WHERE  task_id IN ({})
+             GROUP  BY task_id",
+            vec!["?"; chunk.len()].join(", ")
+        );
+        let mut stmt = conn.prepare_cached(&sql)?;
+        let params = [i64::MIN, i64::MAX].iter().chain(chunk);
+        let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        out.extend(rows.filter_map(Result::ok));
+    }
+    Ok(out)
+}
+
+/// Assemble a node and its children, rolling totals up from the leaves.
+/// Each child list is taken out of `by_parent` once, so a cycle in the
+/// stored data can't recurse forever.
+fn build(task: Task, by_parent: &mut HashMap<i64, Vec<Task>>, tracked: &HashMap<i64, i64>) -> TaskNode {
+    let children: Vec<TaskNode> = by_parent
+        .remove(&task.id)
+        .unwrap_or_default()
+        .into_iter()
+        .map(|child| build(child, by_parent, tracked))
+        .collect();
+
+    let mut rollup = TaskRollup {
+        tracked_seconds:  tracked.get(&task.id).copied().unwrap_or(0),
+        estimate_minutes: task.estimate_minutes.unwrap_or(0),
+        ..TaskRollup::default()
+    };
+    for child in &children {
+        rollup.tracked_seconds  += child.rollup.tracked_seconds;
+        rollup.estimate_minutes += child.rollup.estimate_minutes;
+        rollup.subtasks_total   += 1 + child.rollup.subtasks_total;
+        rollup.subtasks_done    += child.rollup.subtasks_done + i64::from(child.task.completed_at.is_some());
+    }
+    rollup.percent_done = (rollup.subtasks_total > 0)
+        .then(|| rollup.subtasks_done as f64 * 100.0 / rollup.subtasks_total as f64);
+
+    TaskNode { task, children, rollup }
+}
@@ -2,18 +2,37 @@
 //
 // Task CRUD: create, read, update, delete, search, and tag-linking helpers.
 
+use std::collections::{HashMap, HashSet};
+
 use chrono::Utc;
 use rusqlite::{Connection, Result};
 
-use super::models::{Tag, Task};
+use super::calendar::Calendar;
+use super::models::{StatusChange, Tag, Task, TaskQuery};
+use super::{recurrence, task_query, workflows};
+
+/// Column list expected by `map_task_rows`, for queries aliasing tasks as `t`.
+pub(super) const TASK_COLUMNS: &str =
+    "t.id, t.title, t.description, t.due_date, t.priority,
+     t.status, t.project_id, t.parent_id, t.position,
+     t.external_id, t.source, t.created_at, t.completed_at,
+     t.estimate_minutes, t.recurrence,
+     EXISTS (SELECT 1 FROM task_dependencies d
+             JOIN tasks b ON b.id = d.blocked_by
+             WHERE d.task_id = t.id AND b.completed_at IS NULL) AS blocked";
 
 /// Insert a new task row and attach its tags. Returns the new row id.
+/// The status isn't checked against the workflow (see `workflows::check`),
+/// but a done status sets `completed_at`.
 pub fn create(conn: &Connection, task: Task) -> Result<i64> {
+    let now = Utc::now().timestamp();
+    let done = workflows::for_project(conn, task.project_id)?.is_done(&task.status);
     conn.execute(
         "INSERT INTO tasks
              (title, description, due_date, priority, status,
-              project_id, parent_id, position, external_id, source, created_at)
-         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+              project_id, parent_id, position, external_id, source, created_at,
+              estimate_minutes, recurrence, completed_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
         (
             &task.title,
             &task.description,
@@ -25,7 +44,10 @@ pub fn create(conn: &Connection, task: Task) -> Result<i64> {
             task.position,
             &task.external_id,
             &task.source,
-            Utc::now().timestamp(),
+            now,
+            task.estimate_minutes,
+            recurrence::to_column(task.recurrence.as_ref()),
+            done.then_some(now),
         ),
     )?;
     let task_id = conn.last_insert_rowid();
@@ -33,6 +55,21 @@ pub fn create(conn: &Connection, task: Task) -> Result<i64> {
     Ok(task_id)
 }
 
+/// `create` after checking the status against the project's workflow, in
+/// one transaction so two creates can't both take the last WIP slot.
+/// Returns the rejected change instead of an id if the check fails.
+pub fn create_checked(conn: &Connection, task: Task) -> Result<std::result::Result<i64, StatusChange>> {
+    let tx = conn.unchecked_transaction()?;
+    let workflow = workflows::for_project(&tx, task.project_id)?;
+    let change = workflows::check_new(&tx, &workflow, task.project_id, &task.status, 1)?;
+    if change != StatusChange::Allowed {
+        return Ok(Err(change));
+    }
+    let id = create(&tx, task)?;
+    tx.commit()?;
+    Ok(Ok(id))
+}
+
 /// Upsert an external task (from GitHub/GitLab/Jira).
 /// If a task with the same external_id + source already exists, only the
 /// title and description are updated (status, position, tags are preserved).
@@ -64,90 +101,176 @@ pub fn is_imported(conn: &Connection, external_id: &str, source: &str) -> Result
     Ok(count > 0)
 }
 
-/// Overwrite all editable fields of an existing task.
-/// `completed_at` is set on first completion and preserved on subsequent edits;
-/// it is cleared when a task moves out of 'done'.
-pub fn update(conn: &Connection, task: Task) -> Result<()> {
+/// Save every field of a task after checking its status against the
+/// workflow of its (possibly new) project; a rejected change is returned
+/// without saving anything. `completed_at` is set on first completion and
+/// preserved on subsequent edits; it is cleared when the task moves to a
+/// status that isn't done. Completing a recurring task creates its next
+/// occurrence in the same transaction.
+pub fn update(conn: &Connection, calendar: &Calendar, task: Task) -> Result<StatusChange> {
+    let tx = conn.unchecked_transaction()?;
+    let workflow = workflows::for_project(&tx, task.project_id)?;
+    let change = workflows::check(&tx, &workflow, Some(task.id), task.project_id, &task.status)?;
+    if change != StatusChange::Allowed {
+        return Ok(change);
+    }
+
     // Preserve the original completion timestamp
-    let existing_completed_at: Option<i64> = conn
+    let existing_completed_at: Option<i64> = tx
         .prepare("SELECT completed_at FROM tasks WHERE id = ?1")?
         .query_row([task.id], |row| row.get(0))
-        .ok()
-        .flatten();
+        .unwrap_or(None);
+    let was_done = existing_completed_at.is_some();
+    let is_done = workflow.is_done(&task.status);
 
-    let completed_at = if task.status == "done" {
+    let completed_at = if is_done {
         existing_completed_at.or_else(|| Some(Utc::now().timestamp()))
     } else {
         None
     };
 
-    conn.execute(
+    tx.execute(
         "UPDATE tasks
          SET title = ?1, description = ?2, due_date = ?3, priority = ?4,
              status = ?5, project_id = ?6, parent_id = ?7,
-             position = ?8, completed_at = ?9
-         WHERE id = ?10",
+             position = ?8, completed_at = ?9, estimate_minutes = ?10,
+             recurrence = ?11
+         WHERE id = ?12",
         (
             &task.title, &task.description, task.due_date, task.priority,
             &task.status, task.project_id, task.parent_id, task.position,
-            completed_at, task.id,
+            completed_at, task.estimate_minutes,
+            recurrence::to_column(task.recurrence.as_ref()), task.id,
         ),
     )?;
 
     // Replace the tag list atomically: delete all then re-insert
-    conn.execute("DELETE FROM task_tags WHERE task_id = ?1", [task.id])?;
-    set_tags(conn, task.id, &task.tags);
+    tx.execute("DELETE FROM task_tags WHERE task_id = ?1", [task.id])?;
+    set_tags(&tx, task.id, &task.tags);
 
-    Ok(())
+    if is_done && !was_done {
+        recurrence::spawn_next(&tx, calendar, task.id)?;
+    }
+    tx.commit()?;
+    Ok(StatusChange::Allowed)
+}
+
+/// Rewrite `position` for `ids` in the given order (0, 1, 2, …) in one
+/// transaction. Fails with `QueryReturnedNoRows`, changing nothing, if any
+/// id doesn't exist. Repeated ids keep their first place.
+pub fn reorder(conn: &Connection, ids: &[i64]) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    write_positions(&tx, ids)?;
+    tx.commit()
 }
 
-/// Delete a task by id. Tags are removed automatically via ON DELETE CASCADE.
+/// Move a task into the `status` column and rewrite that column's order as
+/// `column_ids`, atomically. The task is appended if `column_ids` omits it.
+/// The workflow check, `completed_at` and recurrence follow the same rules
+/// as `update`; a rejected move changes nothing.
+pub fn move_to_column(
+    conn:       &Connection,
+    calendar:   &Calendar,
+    id:         i64,
+    status:     &str,
+    column_ids: &[i64],
+) -> Result<StatusChange> {
+    let tx = conn.unchecked_transaction()?;
+    let change = set_status(&tx, calendar, id, status)?;
+    if change != StatusChange::Allowed {
+        return Ok(change);
+    }
+
+    let mut order = column_ids.to_vec();
+    if !order.contains(&id) {
+        order.push(id);
+    }
+    write_positions(&tx, &order)?;
+    tx.commit()?;
+    Ok(change)
+}
+
+/// Change only a task's status, with the same workflow check, `completed_at`
+/// and recurrence rules as `update`. Fails with `QueryReturnedNoRows` if the
+/// task doesn't exist. Runs inside the caller's transaction.
+pub(super) fn set_status(conn: &Connection, calendar: &Calendar, id: i64, status: &str) -> Result<StatusChange> {
+    let (project_id, was_done): (Option<i64>, bool) = conn.query_row(
+        "SELECT project_id, completed_at IS NOT NULL FROM tasks WHERE id = ?1",
+        [id],
+        |row| Ok((row.get(0)?, row.get(1)?)),
+    )?;
+    let workflow = workflows::for_project(conn, project_id)?;
+    let change = workflows::check(conn, &workflow, Some(id), project_id, status)?;
+    if change != StatusChange::Allowed {
+        return Ok(change);
+    }
+
+    let is_done = workflow.is_done(status);
+    conn.execute(
+        "UPDATE tasks
+         SET completed_at = CASE
+                 WHEN ?2 THEN COALESCE(completed_at, ?3)
+                 ELSE NULL
+             END,
+             status = ?4
+         WHERE id = ?1",
+        (id, is_done, Utc::now().timestamp(), status),
+    )?;
+    if is_done && !was_done {
+        recurrence::spawn_next(conn, calendar, id)?;
+    }
+    Ok(change)
+}
+
+/// Delete a task by id. Tags and dependency links are removed automatically
+/// via ON DELETE CASCADE.
 pub fn delete(conn: &Connection, id: i64) -> Result<()> {
     conn.execute("DELETE FROM tasks WHERE id = ?1", [id])?;
     Ok(())
 }
 
-/// Return tasks with optional project / tag / status filters.
-/// Filter values are interpolated as literals rather than params because
-/// rusqlite doesn't support optional bind parameters natively.
+/// Return tasks with optional project / tag / status / actionable filters.
+/// Thin wrapper over `task_query::page` kept for the `get_tasks` command.
 pub fn list(
     conn: &Connection,
     filter_project: Option<i64>,
     filter_tag:     Option<i64>,
     filter_status:  Option<String>,
+    actionable:     bool,
 ) -> Result<Vec<Task>> {
-    // Build the SQL dynamically based on which filters are active
-    let mut sql = "SELECT t.id, t.title, t.description, t.due_date, t.priority,
-                          t.status, t.project_id, t.parent_id, t.position,
-                          t.external_id, t.source, t.created_at, t.completed_at
-                   FROM tasks t ".to_string();
-
-    if filter_tag.is_some() {
-        sql.push_str("JOIN task_tags tt ON t.id = tt.task_id ");
-    }
-    sql.push_str("WHERE 1=1 ");
-
-    if let Some(id) = filter_tag     { sql.push_str(&format!("AND tt.tag_id = {id} ")); }
-    if let Some(id) = filter_project { sql.push_str(&format!("AND t.project_id = {id} ")); }
-    if let Some(s)  = filter_status  { sql.push_str(&format!("AND t.status = '{s}' ")); }
-
-    sql.push_str("ORDER BY t.position ASC, t.created_at DESC");
-
-    map_task_rows(conn, &sql, [])
+    let query = TaskQuery {
+        project_ids: filter_project.into_iter().collect(),
+        tag_ids:     filter_tag.into_iter().collect(),
+        statuses:    filter_status.into_iter().collect(),
+        actionable,
+        ..TaskQuery::default()
+    };
+    Ok(task_query::page(conn, &query, None)?.tasks)
 }
 
 /// Full-text search across task titles and descriptions.
 pub fn search(conn: &Connection, query: &str) -> Result<Vec<Task>> {
-    let sql = "SELECT id, title, description, due_date, priority, status,
-                      project_id, parent_id, position, external_id, source,
-                      created_at, completed_at
-               FROM tasks
-               WHERE title LIKE ?1 OR description LIKE ?1";
-    map_task_rows(conn, sql, [format!("%{query}%")])
+    let sql = format!(
+        "SELECT {TASK_COLUMNS} FROM tasks t
+         WHERE t.title LIKE ?1 OR t.description LIKE ?1"
+    );
+    map_task_rows(conn, &sql, [format!("%{query}%")])
 }
 
 // ── Private helpers ───────────────────────────────────────────────────────
 
+/// Set each task's position to its index in `ids`.
+fn write_positions(conn: &Connection, ids: &[i64]) -> Result<()> {
+    let mut seen = HashSet::new();
+    let mut stmt = conn.prepare_cached("UPDATE tasks SET position = ?1 WHERE id = ?2")?;
+    for (position, id) in ids.iter().filter(|id| seen.insert(**id)).enumerate() {
+        if stmt.execute((position as i64, id))? == 0 {
+            return Err(rusqlite::Error::QueryReturnedNoRows);
+        }
+    }
+    Ok(())
+}
+
 /// Attach a list of tags to a task (INSERT OR IGNORE).
 fn set_tags(conn: &Connection, task_id: i64, tags: &[Tag]) {
     for tag in tags {
@@ -158,33 +281,19 @@ fn set_tags(conn: &Connection, task_id: i64, tags: &[Tag]) {
     }
 }
 
-/// Fetch the tags attached to a single task.
-pub(super) fn get_tags_for(conn: &Connection, task_id: i64) -> Vec<Tag> {
-    let Ok(mut stmt) = conn.prepare(
-        "SELECT id, name, color FROM tags
-         JOIN task_tags ON tags.id = task_tags.tag_id
-         WHERE task_tags.task_id = ?1",
-    ) else { return vec![]; };
-
-    stmt.query_map([task_id], |row| {
-        Ok(Tag { id: row.get(0)?, name: row.get(1)?, color: row.get(2)? })
-    })
-    .map(|rows| rows.filter_map(Result::ok).collect())
-    .unwrap_or_default()
-}
-
 /// Execute a task SELECT query and map every row to a Task (including its tags).
-fn map_task_rows(
+/// Tags for the whole result set are fetched afterwards in batches rather
+/// than one query per task.
+pub(super) fn map_task_rows(
     conn: &Connection,
     sql:  &str,
     params: impl rusqlite::Params,
 ) -> Result<Vec<Task>> {
     let mut stmt = conn.prepare(sql)?;
-    let tasks = stmt
+    let mut tasks: Vec<Task> = stmt
         .query_map(params, |row| {
-            let id: i64 = row.get(0)?;
             Ok(Task {
-                id,
+                id:           row.get(0)?,
                 title:        row.get(1)?,
                 description:  row.get(2)?,
                 due_date:     row.get(3)?,
@@ -198,11 +307,49 @@ fn map_task_rows(
                 created_at:   row.get(11)?,
                 completed_at: row.get(12)?,
                 tags:         vec![], // populated below, outside the borrow
+                estimate_minutes: row.get(13)?,
+                recurrence:   recurrence::from_column(row.get(14)?),
+                blocked:      row.get(15)?,
             })
         })?
         .filter_map(Result::ok)
-        // Load each task's tags after the statement borrow ends
-        .map(|mut t| { t.tags = get_tags_for(conn, t.id); t })
         .collect();
+
+    let ids: Vec<i64> = tasks.iter().map(|t| t.id).collect();
+    let mut tags_by_task = get_tags_for_many(conn, &ids)?;
+    for task in &mut tasks {
+        if let Some(tags) = tags_by_task.remove(&task.id) {
+            task.tags = tags;
+        }
+    }
     Ok(tasks)
 }
+
+/// Fetch the tags of many tasks at once, keyed by task id.
+/// Ids are chunked to stay well below SQLite's bound-variable limit.
+fn get_tags_for_many(conn: &Connection, task_ids: &[i64]) -> Result<HashMap<i64, Vec<Tag>>> {
+    const CHUNK: usize = 500;
+
+    let mut out: HashMap<i64, Vec<Tag>> = HashMap::new();
+    for chunk in task_ids.chunks(CHUNK) {
+        let sql = format!(
+            "SELECT tt.task_id, tags.id, tags.name, tags.color
+             FROM task_tags tt
+             JOIN tags ON tags.id = tt.tag_id
+             WHERE tt.task_id IN ({})
+             ORDER BY tags.name",
+            vec!["?"; chunk.len()].join(", ")
+        );
+        let mut stmt = conn.prepare_cached(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(chunk), |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                Tag { id: row.get(1)?, name: row.get(2)?, color: row.get(3)? },
+            ))
+        })?;
+        for (task_id, tag) in rows.filter_map(Result::ok) {
+            out.entry(task_id).or_default().push(tag);
+        }
+    }
+    Ok(out)
+}
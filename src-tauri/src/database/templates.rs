@@ -0,0 +1,322 @@
+// database/templates.rs
+//
+// Reusable task templates. A template row holds the root task's fields with
+// its checklist as JSON in `subtasks`; tags are linked through
+// `task_template_tags`. `instantiate` fills in `{placeholders}` and creates
+// the whole tree through `tasks::create`.
+
+use std::collections::HashMap;
+
+use chrono::{Datelike, Utc};
+use rusqlite::{Connection, OptionalExtension, Result, Row};
+
+use super::calendar::Calendar;
+use super::models::{StatusChange, Tag, Task, TaskTemplate, TemplateSubtask};
+use super::{tasks, workflows};
+
+/// Placeholders filled in automatically unless a value is given.
+const BUILT_INS: &[&str] = &["date", "week"];
+
+const TEMPLATE_COLUMNS: &str =
+    "id, name, title, description, project_id, priority, estimate_minutes, subtasks, created_at";
+
+pub fn create(conn: &Connection, template: &TaskTemplate) -> Result<i64> {
+    let tx = conn.unchecked_transaction()?;
+    tx.execute(
+        "INSERT INTO task_templates
+             (name, title, description, project_id, priority, estimate_minutes, subtasks, created_at)
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+        (
+            &template.name,
+            &template.title,
+            &template.description,
+            template.project_id,
+            template.priority,
+            template.estimate_minutes,
+            subtasks_to_column(&template.subtasks),
+            Utc::now().timestamp(),
+        ),
+    )?;
+    let id = tx.last_insert_rowid();
+    set_tags(&tx, id, &template.tags)?;
+    tx.commit()?;
+    Ok(id)
+}
+
+/// All templates, by name.
+pub fn list(conn: &Connection) -> Result<Vec<TaskTemplate>> {
+    let mut stmt = conn.prepare(&format!(
+        "SELECT {TEMPLATE_COLUMNS} FROM task_templates ORDER BY name COLLATE NOCASE"
+    ))?;
+    let mut templates: Vec<TaskTemplate> =
+        stmt.query_map([], map_template)?.filter_map(Result::ok).collect();
+    for template in &mut templates {
+        template.tags = get_tags(conn, template.id)?;
+    }
+    Ok(templates)
+}
+
+pub fn get(conn: &Connection, id: i64) -> Result<Option<TaskTemplate>> {
+    let template = conn
+        .query_row(
+            &format!("SELECT {TEMPLATE_COLUMNS} FROM task_templates WHERE id = ?1"),
+            [id],
+            map_template,
+        )
+        .optional()?;
+    match template {
+        Some(mut template) => {
+            template.tags = get_tags(conn, id)?;
+            Ok(Some(template))
+        }
+        None => Ok(None),
+    }
+}
+
+/// Overwrite a template's fields and tags. `QueryReturnedNoRows` if it doesn't exist.
+pub fn update(conn: &Connection, template: &TaskTemplate) -> Result<()> {
+    let tx = conn.unchecked_transaction()?;
+    let changed = tx.execute(
+        "UPDATE task_templates
+         SET name = ?1, title = ?2, description = ?3, project_id = ?4,
+             priority = ?5, estimate_minutes = ?6, subtasks = ?7
+         WHERE id = ?8",
+        (
+            &template.name,
+            &template.title,
+            &template.description,
+            template.project_id,
+            template.priority,
+            template.estimate_minutes,
+            subtasks_to_column(&template.subtasks),
+            template.id,
+        ),
+    )?;
+    if changed == 0 {
+        return Err(rusqlite::Error::QueryReturnedNoRows);
+    }
+    tx.execute("DELETE FROM task_template_tags WHERE template_id = ?1", [template.id])?;
+    set_tags(&tx, template.id, &template.tags)?;
+    tx.commit()
+}
+
+pub fn delete(conn: &Connection, id: i64) -> Result<()> {
+    conn.execute("DELETE FROM task_templates WHERE id = ?1", [id])?;
+    Ok(())
+}
+
+/// Create a task tree from template `id`, filling placeholders from `values`
+/// (plus the built-ins, dated by `calendar`). The root gets `due_date`; every
+/// task starts in the initial status of the template project's workflow,
+/// which must have room for all of them under its WIP limit. Returns the
+/// root task's id, or the rejected change without creating anything.
+pub fn instantiate(
+    conn:     &Connection,
+    calendar: &Calendar,
+    id:       i64,
+    values:   &HashMap<String, String>,
+    due_date: Option<i64>,
+) -> Result<std::result::Result<i64, StatusChange>> {
+    let template = get(conn, id)?.ok_or(rusqlite::Error::QueryReturnedNoRows)?;
+    let mut values = values.clone();
+    let today = calendar.day(Utc::now().timestamp());
+    values.entry("date".into()).or_insert_with(|| today.format("%Y-%m-%d").to_string());
+    values.entry("week".into()).or_insert_with(|| {
+        let week = today.iso_week();
+        format!("{}-W{:02}", week.year(), week.week())
+    });
+
+    let tx = conn.unchecked_transaction()?;
+    let workflow = workflows::for_project(&tx, template.project_id)?;
+    let status = workflow.initial_status().to_string();
+    let count = 1 + count_subtasks(&template.subtasks);
+    let change = workflows::check_new(&tx, &workflow, template.project_id, &status, count)?;
+    if change != StatusChange::Allowed {
+        return Ok(Err(change));
+    }
+    let root = Task {
+        id: 0,
+        title: fill(&template.title, &values),
+        description: template.description.as_deref().map(|d| fill(d, &values)),
+        due_date,
+        priority: template.priority,
+        status: status.clone(),
+        project_id: template.project_id,
+        parent_id: None,
+        position: 0,
+        external_id: None,
+        source: None,
+        created_at: 0,
+        completed_at: None,
+        tags: template.tags.clone(),
+        estimate_minutes: template.estimate_minutes,
+        recurrence: None,
+        blocked: false,
+    };
+    let root_id = tasks::create(&tx, root)?;
+    create_subtasks(&tx, &template, &status, root_id, &template.subtasks, &values)?;
+    tx.commit()?;
+    Ok(Ok(root_id))
+}
+
+/// Names of the placeholders used anywhere in `template` that need a value
+/// from the caller (built-ins like `{date}` are left out), in order of first use.
+pub fn placeholders(template: &TaskTemplate) -> Vec<String> {
+    let mut texts = vec![template.title.as_str()];
+    texts.extend(template.description.as_deref());
+    collect_texts(&template.subtasks, &mut texts);
+
+    let mut names: Vec<String> = Vec::new();
+    for text in texts {
+        for name in placeholder_names(text) {
+            if !BUILT_INS.contains(&name) && !names.iter().any(|n| n == name) {
+                names.push(name.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// Trim names and titles and reject empty ones before a template is saved.
+pub fn validate(template: &mut TaskTemplate) -> std::result::Result<(), String> {
+    template.name = template.name.trim().to_string();
+    template.title = template.title.trim().to_string();
+    if template.name.is_empty() {
+        return Err("Template name must not be empty.".into());
+    }
+    if template.title.is_empty() {
+        return Err("Template title must not be empty.".into());
+    }
+    validate_subtasks(&mut template.subtasks)
+}
+
+// ── Private helpers ───────────────────────────────────────────────────────
+
+fn map_template(row: &Row) -> Result<TaskTemplate> {
+    Ok(TaskTemplate {
+        id:               row.get(0)?,
+        name:             row.get(1)?,
+        title:            row.get(2)?,
+        description:      row.get(3)?,
+        project_id:       row.get(4)?,
+        priority:         row.get(5)?,
+        estimate_minutes: row.get(6)?,
+        tags:             vec![], // populated by the caller
+        subtasks:         subtasks_from_column(row.get(7)?),
+        created_at:       row.get(8)?,
+    })
+}
+
+fn subtasks_to_column(subtasks: &[TemplateSubtask]) -> String {
+    serde_json::to_string(subtasks).unwrap_or_else(|_| "[]".into())
+}
+
+/// Unreadable checklists are treated as empty.
+fn subtasks_from_column(raw: Option<String>) -> Vec<TemplateSubtask> {
+    raw.and_then(|r| serde_json::from_str(&r).ok()).unwrap_or_default()
+}
+
+fn get_tags(conn: &Connection, template_id: i64) -> Result<Vec<Tag>> {
+    let mut stmt = conn.prepare(
+        "SELECT tags.id, tags.name, tags.color
+         FROM task_template_tags tt
+         JOIN tags ON tags.id = tt.tag_id
+         WHERE tt.template_id = ?1
+         ORDER BY tags.name",
+    )?;
+    let tags = stmt
+        .query_map([template_id], |row| {
+            Ok(Tag { id: row.get(0)?, name: row.get(1)?, color: row.get(2)? })
+        })?
+        .filter_map(Result::ok)
+        .collect();
+    Ok(tags)
+}
+
+fn set_tags(conn: &Connection, template_id: i64, tags: &[Tag]) -> Result<()> {
+    for tag in tags {
+        conn.execute(
+            "INSERT OR IGNORE INTO task_template_tags (template_id, tag_id) VALUES (?1, ?2)",
+            (template_id, tag.id),
+        )?;
+    }
+    Ok(())
+}
+
+fn create_subtasks(
+    conn:      &Connection,
+    template:  &TaskTemplate,
+    status:    &str,
+    parent_id: i64,
+    subtasks:  &[TemplateSubtask],
+    values:    &HashMap<String, String>,
+) -> Result<()> {
+    for (position, item) in subtasks.iter().enumerate() {
+        let task = Task {
+            id: 0,
+            title: fill(&item.title, values),
+            description: item.description.as_deref().map(|d| fill(d, values)),
+            due_date: None,
+            priority: template.priority,
+            status: status.to_string(),
+            project_id: template.project_id,
+            parent_id: Some(parent_id),
+            position: position as i32,
+            external_id: None,
+            source: None,
+            created_at: 0,
+            completed_at: None,
+            tags: vec![],
+            estimate_minutes: item.estimate_minutes,
+            recurrence: None,
+            blocked: false,
+        };
+        let id = tasks::create(conn, task)?;
+        create_subtasks(conn, template, status, id, &item.subtasks, values)?;
+    }
+    Ok(())
+}
+
+fn count_subtasks(subtasks: &[TemplateSubtask]) -> i64 {
+    subtasks.iter().map(|item| 1 + count_subtasks(&item.subtasks)).sum()
+}
+
+fn validate_subtasks(subtasks: &mut [TemplateSubtask]) -> std::result::Result<(), String> {
+    for item in subtasks {
+        item.title = item.title.trim().to_string();
+        if item.title.is_empty() {
+            return Err("Template subtasks need a title.".into());
+        }
+        validate_subtasks(&mut item.subtasks)?;
+    }
+    Ok(())
+}
+
+fn collect_texts<'a>(subtasks: &'a [TemplateSubtask], out: &mut Vec<&'a str>) {
+    for item in subtasks {
+        out.push(&item.title);
+        out.extend(item.description.as_deref());
+        collect_texts(&item.subtasks, out);
+    }
+}
+
+/// Names inside `{…}` that consist of letters, digits and underscores;
+/// any other braces are plain text.
+fn placeholder_names(text: &str) -> impl Iterator<Item = &str> {
+    text.split('{').skip(1).filter_map(|part| {
+        let name = part.split_once('}')?.0;
+        let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        valid.then_some(name)
+    })
+}
+
+/// Replace each `{name}` that has a value; unknown placeholders are kept as-is.
+fn fill(text: &str, values: &HashMap<String, String>) -> String {
+    let mut out = text.to_string();
+    for name in placeholder_names(text) {
+        if let Some(value) = values.get(name) {
+            out = out.replace(&format!("{{{name}}}"), value);
+        }
+    }
+    out
+}
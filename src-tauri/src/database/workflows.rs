@@ -0,0 +1,307 @@
+// database/workflows.rs
+//
+// Per-project workflows: the ordered status columns, which of them count as
+// done, WIP limits and allowed transitions. A project's workflow lives as
+// JSON in `projects.workflow`; tasks without a project, or in a project
+// without one, use `Workflow::default()` ("todo", "doing", "done").
+//
+// Whether a task is done is decided here when its status changes and kept
+// in `completed_at`, so queries test `completed_at IS NOT NULL` rather than
+// a status name.
+
+use std::collections::HashMap;
+
+use chrono::Utc;
+use rusqlite::{Connection, OptionalExtension, Result};
+
+use super::models::{StatusChange, Workflow, WorkflowStatus};
+
+impl Default for Workflow {
+    fn default() -> Self {
+        let status = |key: &str, name: &str, done: bool| WorkflowStatus {
+            key: key.into(),
+            name: name.into(),
+            done,
+            wip_limit: None,
+        };
+        Workflow {
+            statuses: vec![
+                status("todo", "To Do", false),
+                status("doing", "Doing", false),
+                status("done", "Done", true),
+            ],
+            transitions: vec![],
+            import_map: HashMap::new(),
+        }
+    }
+}
+
+impl Workflow {
+    pub fn status(&self, key: &str) -> Option<&WorkflowStatus> {
+        self.statuses.iter().find(|s| s.key == key)
+    }
+
+    pub fn is_done(&self, key: &str) -> bool {
+        self.status(key).is_some_and(|s| s.done)
+    }
+
+    /// The first open column, where new tasks start.
+    pub fn initial_status(&self) -> &str {
+        self.statuses.iter().find(|s| !s.done).unwrap_or(&self.statuses[0]).key.as_str()
+    }
+
+    /// The first done column, used when a task is completed without naming one.
+    pub fn done_status(&self) -> &str {
+        self.statuses.iter().find(|s| s.done).unwrap_or(&self.statuses[0]).key.as_str()
+    }
+
+    /// Moving between two different statuses needs a matching transition
+    /// unless the workflow lists none.
+    pub fn allows(&self, from: &str, to: &str) -> bool {
+        from == to
+            || self.transitions.is_empty()
+            || self.transitions.iter().any(|t| t.from == from && t.to == to)
+    }
+
+    /// Map a status from an external tracker onto this workflow: `import_map`
+    /// first, then the usual open / in-progress / closed names (GitHub and
+    /// GitLab states, Jira status categories), then the initial status.
+    pub fn import_status(&self, external: &str) -> &str {
+        let external = external.trim().to_lowercase();
+        let mapped = self
+            .import_map
+            .iter()
+            .find(|(from, _)| from.to_lowercase() == external)
+            .and_then(|(_, to)| self.status(to));
+        if let Some(status) = mapped {
+            return &status.key;
+        }
+        if let Some(status) = self.status(&external) {
+            return &status.key;
+        }
+        match external.as_str() {
+            "closed" | "done" | "resolved" => self.done_status(),
+            "in progress" | "indeterminate" => {
+                self.statuses.iter().filter(|s| !s.done).nth(1).map_or(self.initial_status(), |s| &s.key)
+            }
+            _ => self.initial_status(),
+        }
+    }
+}
+
+/// Serialise a workflow for the `workflow` column.
+pub fn to_column(workflow: Option<&Workflow>) -> Option<String> {
+    workflow.and_then(|w| serde_json::to_string(w).ok())
+}
+
+/// Parse the `workflow` column. Unreadable workflows are treated as none.
+pub fn from_column(raw: Option<String>) -> Option<Workflow> {
+    raw.and_then(|r| serde_json::from_str(&r).ok())
+}
+
+/// The workflow tasks in `project_id` follow.
+pub fn for_project(conn: &Connection, project_id: Option<i64>) -> Result<Workflow> {
+    let Some(project_id) = project_id else { return Ok(Workflow::default()) };
+    let raw: Option<Option<String>> = conn
+        .query_row("SELECT workflow FROM projects WHERE id = ?1", [project_id], |row| row.get(0))
+        .optional()?;
+    Ok(from_column(raw.flatten()).filter(|w| !w.statuses.is_empty()).unwrap_or_default())
+}
+
+/// Give a project its own workflow, or go back to the default with `None`.
+/// Returns the statuses its tasks still use that the new workflow lacks;
+/// if there are any, nothing is changed. Tasks whose status now counts as
+/// done (or no longer does) get `completed_at` set (or cleared).
+pub fn set(conn: &Connection, project_id: i64, workflow: Option<&Workflow>) -> Result<Vec<String>> {
+    let tx = conn.unchecked_transaction()?;
+    tx.query_row("SELECT 1 FROM projects WHERE id = ?1", [project_id], |_| Ok(()))?;
+
+    let effective = workflow.cloned().unwrap_or_default();
+    let mut stmt = tx.prepare("SELECT DISTINCT status FROM tasks WHERE project_id = ?1 ORDER BY status")?;
+    let orphaned: Vec<String> = stmt
+        .query_map([project_id], |row| row.get(0))?
+        .filter_map(Result::ok)
+        .filter(|status: &String| effective.status(status).is_none())
+        .collect();
+    drop(stmt);
+    if !orphaned.is_empty() {
+        return Ok(orphaned);
+    }
+
+    tx.execute(
+        "UPDATE projects SET workflow = ?1 WHERE id = ?2",
+        (to_column(workflow), project_id),
+    )?;
+    refresh_completed(&tx, &effective, Some(project_id), None)?;
+    tx.commit()?;
+    Ok(vec![])
+}
+
+/// Check moving task `task_id` (or a new task, with `None`) into `to` in
+/// `project_id`, whose workflow is `workflow`. Keeping the same status in the
+/// same project is always allowed; transitions only apply within a project.
+pub fn check(
+    conn:       &Connection,
+    workflow:   &Workflow,
+    task_id:    Option<i64>,
+    project_id: Option<i64>,
+    to:         &str,
+) -> Result<StatusChange> {
+    let current: Option<(String, Option<i64>)> = match task_id {
+        Some(id) => conn
+            .query_row("SELECT status, project_id FROM tasks WHERE id = ?1", [id], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })
+            .optional()?,
+        None => None,
+    };
+    let same_project = current.as_ref().is_some_and(|(_, p)| *p == project_id);
+    if same_project && current.as_ref().is_some_and(|(s, _)| s == to) {
+        return Ok(StatusChange::Allowed);
+    }
+
+    let Some(target) = workflow.status(to) else { return Ok(StatusChange::UnknownStatus) };
+    if let Some((from, _)) = current.as_ref().filter(|_| same_project) {
+        if workflow.status(from).is_some() && !workflow.allows(from, to) {
+            return Ok(StatusChange::NotAllowed);
+        }
+    }
+    if over_wip_limit(conn, target, project_id, task_id, 1)? {
+        return Ok(StatusChange::WipLimitReached);
+    }
+    Ok(StatusChange::Allowed)
+}
+
+/// Check adding `count` new tasks in status `to` to `project_id`, e.g. a
+/// task created from a template together with its subtasks.
+pub fn check_new(
+    conn:       &Connection,
+    workflow:   &Workflow,
+    project_id: Option<i64>,
+    to:         &str,
+    count:      i64,
+) -> Result<StatusChange> {
+    let Some(target) = workflow.status(to) else { return Ok(StatusChange::UnknownStatus) };
+    if over_wip_limit(conn, target, project_id, None, count)? {
+        return Ok(StatusChange::WipLimitReached);
+    }
+    Ok(StatusChange::Allowed)
+}
+
+/// Set or clear `completed_at` to match `workflow` for the tasks of
+/// `project_id`, or only for `task_id`. Existing completion times are kept.
+pub(super) fn refresh_completed(
+    conn:       &Connection,
+    workflow:   &Workflow,
+    project_id: Option<i64>,
+    task_id:    Option<i64>,
+) -> Result<()> {
+    let done: Vec<&str> = workflow.statuses.iter().filter(|s| s.done).map(|s| s.key.as_str()).collect();
+    conn.execute(
+        "UPDATE tasks
+         SET completed_at = CASE
+                 WHEN status IN (SELECT value FROM json_each(?1)) THEN COALESCE(completed_at, ?2)
+                 ELSE NULL
+             END
+         WHERE project_id IS ?3 AND (?4 IS NULL OR id = ?4)",
+        (
+            serde_json::to_string(&done).unwrap_or_else(|_| "[]".into()),
+            Utc::now().timestamp(),
+            project_id,
+            task_id,
+        ),
+    )?;
+    Ok(())
+}
+
+/// Fit the tasks of `project_id` to its workflow after they were moved in
+/// from other projects: a status the workflow lacks becomes its done status
+/// for completed tasks and its initial status otherwise, and `completed_at`
+/// is refreshed. WIP limits aren't applied to such bulk moves.
+pub(super) fn adopt_tasks(conn: &Connection, project_id: Option<i64>) -> Result<()> {
+    let workflow = for_project(conn, project_id)?;
+    let keys: Vec<&str> = workflow.statuses.iter().map(|s| s.key.as_str()).collect();
+    conn.execute(
+        "UPDATE tasks
+         SET status = CASE WHEN completed_at IS NULL THEN ?2 ELSE ?3 END
+         WHERE project_id IS ?1
+           AND status NOT IN (SELECT value FROM json_each(?4))",
+        (
+            project_id,
+            workflow.initial_status(),
+            workflow.done_status(),
+            serde_json::to_string(&keys).unwrap_or_else(|_| "[]".into()),
+        ),
+    )?;
+    refresh_completed(conn, &workflow, project_id, None)
+}
+
+/// Reject workflows that tasks couldn't be managed with: no statuses,
+/// blank or repeated keys, no done status, zero WIP limits, or transitions
+/// and import mappings naming unknown statuses. Keys and names are trimmed.
+pub fn validate(workflow: &mut Workflow) -> std::result::Result<(), String> {
+    for status in &mut workflow.statuses {
+        status.key = status.key.trim().to_string();
+        status.name = status.name.trim().to_string();
+        if status.name.is_empty() {
+            status.name = status.key.clone();
+        }
+    }
+    if workflow.statuses.is_empty() {
+        return Err("A workflow needs at least one status.".into());
+    }
+    for (i, status) in workflow.statuses.iter().enumerate() {
+        if status.key.is_empty() {
+            return Err("Workflow statuses need a key.".into());
+        }
+        if workflow.statuses[..i].iter().any(|s| s.key == status.key) {
+            return Err(format!("Workflow status \"{}\" is listed twice.", status.key));
+        }
+        if status.wip_limit == Some(0) {
+            return Err(format!("The WIP limit of \"{}\" must be at least 1.", status.key));
+        }
+    }
+    if !workflow.statuses.iter().any(|s| s.done) {
+        return Err("A workflow needs at least one done status.".into());
+    }
+    let unknown = workflow
+        .transitions
+        .iter()
+        .flat_map(|t| [&t.from, &t.to])
+        .chain(workflow.import_map.values())
+        .find(|key| workflow.status(key).is_none());
+    if let Some(key) = unknown {
+        return Err(format!("Unknown workflow status \"{key}\"."));
+    }
+    Ok(())
+}
+
+/// Whether `adding` more tasks in `status` (not counting `exclude`, the
+/// task being moved) would go past its WIP limit.
+fn over_wip_limit(
+    conn:       &Connection,
+    status:     &WorkflowStatus,
+    project_id: Option<i64>,
+    exclude:    Option<i64>,
+    adding:     i64,
+) -> Result<bool> {
+    let Some(limit) = status.wip_limit else { return Ok(false) };
+    let count: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM tasks WHERE project_id IS ?1 AND status = ?2 AND id IS NOT ?3",
+        (project_id, &status.key, exclude),
+        |row| row.get(0),
+    )?;
+    Ok(count + adding > i64::from(limit))
+}
+
+impl StatusChange {
+    /// The error to report for a rejected change to `status`, or `None` if allowed.
+    pub fn error(self, status: &str) -> Option<String> {
+        match self {
+            StatusChange::Allowed         => None,
+            StatusChange::UnknownStatus   => Some(format!("\"{status}\" is not a status of this project's workflow.")),
+            StatusChange::NotAllowed      => Some(format!("The workflow doesn't allow moving this task to \"{status}\".")),
+            StatusChange::WipLimitReached => Some(format!("\"{status}\" is at its WIP limit.")),
+        }
+    }
+}
@@ -90,7 +90,7 @@ pub async fn fetch_github_tasks(token: &str, repo: Option<&str>) -> Result<Vec<E
                 description: issue["body"].as_str().map(|s| {
                     if s.len() > 500 { format!("{}…", &s[..500]) } else { s.to_string() }
                 }),
-                status: "todo".to_string(),
+                status: issue["state"].as_str().unwrap_or("open").to_string(),
                 url: issue["html_url"].as_str().unwrap_or("").to_string(),
                 labels,
                 project,
@@ -171,7 +171,7 @@ pub async fn fetch_jira_tasks(domain: &str, email: &str, token: &str) -> Result<
                 description: None,
                 status: issue["fields"]["status"]["statusCategory"]["name"]
                     .as_str()
-                    .unwrap_or("todo")
+                    .unwrap_or("To Do")
                     .to_string(),
                 url: format!("https://{}/browse/{}", clean_domain, key),
                 labels,
@@ -3,14 +3,18 @@
 // This file registers all backend modules and exposes the Tauri commands
 // to the frontend. Business logic lives in the sub-modules, not here.
 
+pub mod billing;
 pub mod commands;
 pub mod database;
 pub mod integrations;
+pub mod vault;
 
+use commands::encryption::DatabasePath;
 use commands::timer::TimerState;
 use database::Database;
 use std::sync::{Arc, Mutex};
-use tauri::Manager;
+use tauri::{AppHandle, Manager};
+use vault::Vault;
 
 pub fn run() {
     tauri::Builder::default()
@@ -28,14 +32,18 @@ pub fn run() {
                 .app_data_dir()
                 .expect("Failed to resolve app data directory");
             std::fs::create_dir_all(&app_dir).ok();
-            let db_path = app_dir.join("code_chrono.db");
+            let db_path = app_dir.join("code_chrono.db").to_str().unwrap().to_string();
 
-            let db = Database::new(db_path.to_str().unwrap())
-                .expect("Failed to open database");
-
-            let db_arc = Arc::new(Mutex::new(db));
-            app.manage(Arc::clone(&db_arc));
             app.manage(Arc::new(Mutex::new(TimerState::default())));
+            app.manage(DatabasePath(db_path.clone()));
+
+            // An encrypted database stays closed until the frontend calls
+            // `unlock_database`, which finishes startup from there.
+            if !Database::is_encrypted_file(&db_path) {
+                let db = Database::new(&db_path).expect("Failed to open database");
+                let vault = Vault::open(&db, &app_dir.join(vault::KEY_FILE), None)?;
+                start_services(app.handle(), Arc::new(db), Arc::new(vault));
+            }
 
             // macOS requires a runtime permission request before any notification can be shown.
             // We fire-and-forget at startup; the user will see the system dialog on first launch.
@@ -47,12 +55,6 @@ pub fn run() {
                 let _ = handle.notification().request_permission();
             });
 
-            // Spawn the background IDE / program watcher
-            commands::programs::spawn_program_watcher(
-                Arc::clone(&db_arc),
-                app.handle().clone(),
-            );
-
             Ok(())
         })
         // ── Tauri commands (sub-module paths required by generate_handler!) ─
@@ -70,21 +72,62 @@ pub fn run() {
             commands::tasks::update_task,
             commands::tasks::delete_task,
             commands::tasks::get_tasks,
+            commands::tasks::query_tasks,
             commands::tasks::search_tasks,
+            commands::tasks::reorder_tasks,
+            commands::tasks::move_task_to_column,
+            commands::tasks::batch_update_tasks,
+            commands::tasks::get_task_tree,
+            commands::tasks::move_task,
+            commands::tasks::complete_task,
+            commands::tasks::add_task_dependency,
+            commands::tasks::remove_task_dependency,
+            commands::tasks::get_dependency_graph,
+            // Templates
+            commands::templates::create_template,
+            commands::templates::get_templates,
+            commands::templates::update_template,
+            commands::templates::delete_template,
+            commands::templates::get_template_placeholders,
+            commands::templates::instantiate_template,
             // Projects
             commands::projects::create_project,
             commands::projects::get_projects,
+            commands::projects::update_project,
+            commands::projects::rename_project,
+            commands::projects::merge_projects,
+            commands::projects::set_project_archived,
+            commands::projects::get_budget_status,
+            commands::projects::get_workflow,
+            commands::projects::set_workflow,
             commands::projects::delete_project,
             // Tags
             commands::tags::create_tag,
+            commands::tags::update_tag,
+            commands::tags::merge_tags,
             commands::tags::get_tags,
             commands::tags::delete_tag,
             // Statistics
             commands::stats::get_task_stats,
             commands::stats::get_daily_breakdown,
+            commands::stats::get_grouped_stats,
+            commands::stats::get_trends,
+            commands::stats::get_trend_series,
+            commands::stats::get_focus_heatmap,
+            commands::stats::get_focus_metrics,
+            commands::stats::get_switch_stats,
+            // Billing
+            commands::billing::get_billing_report,
+            commands::billing::render_invoice_draft,
             // Settings
+            commands::settings::get_settings,
+            commands::settings::update_settings,
+            commands::settings::export_settings_profile,
+            commands::settings::import_settings_profile,
             commands::settings::get_setting,
             commands::settings::set_setting,
+            commands::settings::set_secret,
+            commands::settings::get_secret_status,
             // Data
             commands::data::export_csv,
             commands::data::import_csv,
@@ -98,6 +141,12 @@ pub fn run() {
             commands::sync::preview_sync_jira,
             commands::sync::preview_sync_gitlab,
             commands::sync::import_selected,
+            // Encryption at rest
+            commands::encryption::get_encryption_status,
+            commands::encryption::unlock_database,
+            commands::encryption::enable_encryption,
+            commands::encryption::change_passphrase,
+            commands::encryption::disable_encryption,
             // Notifications
             commands::notifications::request_notification_permission,
             commands::notifications::show_notification,
@@ -111,3 +160,20 @@ pub fn run() {
         .run(tauri::generate_context!())
         .expect("Error while running application");
 }
+
+/// Register the open database and secrets vault as managed state and start
+/// the background loops that depend on them. Runs from `setup`, or from
+/// `unlock_database` once an encrypted database has been opened.
+pub(crate) fn start_services(handle: &AppHandle, db: Arc<Database>, vault: Arc<Vault>) {
+    // `manage` refuses a second registration; bail so loops aren't doubled
+    if !handle.manage(Arc::clone(&db)) {
+        return;
+    }
+    handle.manage(vault);
+    handle.manage(commands::settings::watch_settings(handle, &db));
+
+    // Spawn the background IDE / program watcher
+    commands::programs::spawn_program_watcher(Arc::clone(&db), handle.clone());
+    // Spawn the due-date reminder scheduler
+    commands::reminders::spawn_reminder_scheduler(db, handle.clone());
+}
@@ -0,0 +1,211 @@
+// vault.rs — Encrypted secrets vault for integration credentials
+//
+// API tokens are encrypted with ChaCha20-Poly1305 before they reach SQLite.
+// The key comes from one of two places:
+//   • a random 32-byte master key file next to the database (default), or
+//   • the database passphrase stretched with Argon2id, when encryption at
+//     rest is enabled — then no file on disk can decrypt the tokens alone.
+//
+// Secrets are write-only from the frontend's point of view: it can set or
+// clear them and ask whether one is configured, but never read one back.
+
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{PoisonError, RwLock};
+
+use argon2::Argon2;
+use base64::{engine::general_purpose::STANDARD, Engine as _};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use serde::Serialize;
+
+use crate::database::{Database, SecretRow};
+
+/// Credential keys. They used to live in the settings table and are kept
+/// out of it now: `get_setting` / `set_setting` refuse them.
+pub const SECRET_KEYS: &[&str] = &["github_token", "gitlab_token", "jira_token"];
+
+/// Master key file name, kept in the app data directory beside the database.
+pub const KEY_FILE: &str = "vault.key";
+
+/// Settings key holding the Argon2 salt for passphrase-derived keys.
+const SALT_SETTING: &str = "vault_salt";
+
+pub fn is_secret_key(key: &str) -> bool {
+    SECRET_KEYS.contains(&key)
+}
+
+/// What the frontend may learn about a secret.
+#[derive(Serialize, Clone, Debug)]
+pub struct SecretStatus {
+    pub name:       String,
+    pub configured: bool,
+}
+
+pub struct Vault {
+    key_file: PathBuf,
+    cipher:   RwLock<ChaCha20Poly1305>,
+}
+
+impl Vault {
+    /// Open the vault, keyed from `passphrase` when the database is
+    /// encrypted and from the master key file otherwise. Credentials still
+    /// sitting in plaintext settings are moved into the vault.
+    pub fn open(db: &Database, key_file: &Path, passphrase: Option<&str>) -> Result<Self, String> {
+        let key = derive_key(db, key_file, passphrase)?;
+        let vault = Vault {
+            key_file: key_file.to_path_buf(),
+            cipher:   RwLock::new(ChaCha20Poly1305::new(&key)),
+        };
+        vault.adopt_plaintext_settings(db)?;
+        Ok(vault)
+    }
+
+    /// Decrypt the secret stored under `name`.
+    pub fn get(&self, db: &Database, name: &str) -> Result<Option<String>, String> {
+        let Some((nonce, ciphertext)) = db.get_secret(name).map_err(|e| e.to_string())? else {
+            return Ok(None);
+        };
+        let cipher = self.cipher.read().unwrap_or_else(PoisonError::into_inner);
+        decrypt(&cipher, &nonce, &ciphertext).map(Some)
+    }
+
+    /// Encrypt and store `value` under `name`. An empty value clears it.
+    pub fn set(&self, db: &Database, name: &str, value: &str) -> Result<(), String> {
+        if !is_secret_key(name) {
+            return Err(format!("Unknown secret: {name}"));
+        }
+        if value.is_empty() {
+            return db.delete_secret(name).map_err(|e| e.to_string());
+        }
+        let cipher = self.cipher.read().unwrap_or_else(PoisonError::into_inner);
+        let (nonce, ciphertext) = encrypt(&cipher, value)?;
+        db.set_secret(name, &nonce, &ciphertext).map_err(|e| e.to_string())
+    }
+
+    /// Whether each known secret is configured — never the values.
+    pub fn status(db: &Database) -> Result<Vec<SecretStatus>, String> {
+        let stored = db.get_secret_names().map_err(|e| e.to_string())?;
+        Ok(SECRET_KEYS
+            .iter()
+            .map(|name| SecretStatus {
+                name:       name.to_string(),
+                configured: stored.iter().any(|s| s == name),
+            })
+            .collect())
+    }
+
+    /// Re-encrypt every secret for a new database passphrase, or back under
+    /// the master key file when `None`. Written in one transaction.
+    pub fn rekey(&self, db: &Database, passphrase: Option<&str>) -> Result<(), String> {
+        let new_cipher = ChaCha20Poly1305::new(&derive_key(db, &self.key_file, passphrase)?);
+        let mut cipher = self.cipher.write().unwrap_or_else(PoisonError::into_inner);
+
+        let rows = db
+            .get_all_secrets()
+            .map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|(name, nonce, ciphertext)| {
+                let plain = decrypt(&cipher, &nonce, &ciphertext)?;
+                let (nonce, ciphertext) = encrypt(&new_cipher, &plain)?;
+                Ok((name, nonce, ciphertext))
+            })
+            .collect::<Result<Vec<SecretRow>, String>>()?;
+
+        db.replace_secrets(&rows).map_err(|e| e.to_string())?;
+        *cipher = new_cipher;
+        Ok(())
+    }
+
+    /// Move credentials saved by older versions out of the settings table.
+    fn adopt_plaintext_settings(&self, db: &Database) -> Result<(), String> {
+        let cipher = self.cipher.read().unwrap_or_else(PoisonError::into_inner);
+        for name in SECRET_KEYS {
+            let Some(value) = db.get_setting(name).map_err(|e| e.to_string())? else {
+                continue;
+            };
+            if value.is_empty() {
+                db.delete_setting(name).map_err(|e| e.to_string())?;
+                continue;
+            }
+            let (nonce, ciphertext) = encrypt(&cipher, &value)?;
+            db.adopt_setting_as_secret(name, &nonce, &ciphertext)
+                .map_err(|e| e.to_string())?;
+        }
+        Ok(())
+    }
+}
+
+// ── Key handling ──────────────────────────────────────────────────────────
+
+fn derive_key(db: &Database, key_file: &Path, passphrase: Option<&str>) -> Result<Key, String> {
+    match passphrase {
+        Some(passphrase) => {
+            let salt = salt(db)?;
+            let mut key = Key::default();
+            Argon2::default()
+                .hash_password_into(passphrase.as_bytes(), &salt, &mut key)
+                .map_err(|e| e.to_string())?;
+            Ok(key)
+        }
+        None => read_or_create_key_file(key_file),
+    }
+}
+
+/// Load the per-install Argon2 salt, generating it on first use.
+/// The salt isn't secret, so the settings table is fine for it.
+fn salt(db: &Database) -> Result<Vec<u8>, String> {
+    if let Some(encoded) = db.get_setting(SALT_SETTING).map_err(|e| e.to_string())? {
+        return STANDARD.decode(encoded).map_err(|e| e.to_string());
+    }
+    let mut salt = vec![0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    db.set_setting(SALT_SETTING, &STANDARD.encode(&salt))
+        .map_err(|e| e.to_string())?;
+    Ok(salt)
+}
+
+/// Read the 32-byte master key, creating it (owner-only on Unix) if missing.
+fn read_or_create_key_file(path: &Path) -> Result<Key, String> {
+    if let Ok(bytes) = std::fs::read(path) {
+        if bytes.len() != 32 {
+            return Err(format!("Vault key file {} is corrupt.", path.display()));
+        }
+        return Ok(*Key::from_slice(&bytes));
+    }
+
+    let key = ChaCha20Poly1305::generate_key(&mut OsRng);
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options
+        .open(path)
+        .and_then(|mut file| file.write_all(&key))
+        .map_err(|e| format!("Could not create vault key file: {e}"))?;
+    Ok(key)
+}
+
+// ── AEAD helpers ──────────────────────────────────────────────────────────
+
+fn encrypt(cipher: &ChaCha20Poly1305, plaintext: &str) -> Result<(Vec<u8>, Vec<u8>), String> {
+    let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|_| "Failed to encrypt secret.".to_string())?;
+    Ok((nonce.to_vec(), ciphertext))
+}
+
+fn decrypt(cipher: &ChaCha20Poly1305, nonce: &[u8], ciphertext: &[u8]) -> Result<String, String> {
+    if nonce.len() != 12 {
+        return Err("Stored secret is corrupt.".into());
+    }
+    let plain = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Failed to decrypt secret — the vault key has changed.".to_string())?;
+    String::from_utf8(plain).map_err(|e| e.to_string())
+}
@@ -39,6 +39,10 @@ export const br: Strings = {
     allTags: 'Todas as tags',
     allStatuses: 'Todos os status',
     allLabels: 'Todas as etiquetas (labels)',
+    selectedCount: '{n} selecionadas',
+    markDone: 'Marcar como concluídas',
+    moveToProject: 'Mover para projeto',
+    deleteSelected: 'Excluir selecionadas',
     templates: 'Modelos',
     noTemplates: 'Ainda não existem modelos. Salve uma tarefa como modelo para começar.',
     templateNameHint: 'Nome do modelo…',
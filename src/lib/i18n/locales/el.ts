@@ -75,6 +75,10 @@ export const el: Strings = {
     allTags: 'Όλες οι ετικέτες',
     allStatuses: 'Όλες οι καταστάσεις',
     allLabels: 'Όλες οι ετικέτες (labels)',
+    selectedCount: '{n} επιλεγμένες',
+    markDone: 'Σήμανση ως ολοκληρωμένες',
+    moveToProject: 'Μετακίνηση σε έργο',
+    deleteSelected: 'Διαγραφή επιλεγμένων',
     templates: 'Πρότυπα',
     noTemplates: 'Δεν υπάρχουν πρότυπα ακόμα. Αποθηκεύστε μια εργασία ως πρότυπο για να ξεκινήσετε.',
     templateNameHint: 'Όνομα προτύπου…',
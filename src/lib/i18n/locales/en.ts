@@ -37,6 +37,10 @@ export const en = {
     allTags: 'All tags',
     allStatuses: 'All statuses',
     allLabels: 'All labels',
+    selectedCount: '{n} selected',
+    markDone: 'Mark done',
+    moveToProject: 'Move to project',
+    deleteSelected: 'Delete selected',
     templates: 'Templates',
     noTemplates: 'No templates yet. Save a task as a template to get started.',
     templateNameHint: 'Template name…',
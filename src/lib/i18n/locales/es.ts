@@ -39,6 +39,10 @@ export const es: Strings = {
     allTags: 'Todas las etiquetas',
     allStatuses: 'Todos los estados',
     allLabels: 'Todas las etiquetas (labels)',
+    selectedCount: '{n} seleccionadas',
+    markDone: 'Marcar como hechas',
+    moveToProject: 'Mover a proyecto',
+    deleteSelected: 'Eliminar seleccionadas',
     templates: 'Plantillas',
     noTemplates: 'Aún no hay plantillas. Guarda una tarea como plantilla para empezar.',
     templateNameHint: 'Nombre de la plantilla…',
@@ -39,6 +39,10 @@ export const pt: Strings = {
     allTags: 'Todas as etiquetas',
     allStatuses: 'Todos os status',
     allLabels: 'Todas as etiquetas (labels)',
+    selectedCount: '{n} selecionadas',
+    markDone: 'Marcar como concluídas',
+    moveToProject: 'Mover para projeto',
+    deleteSelected: 'Eliminar selecionadas',
     templates: 'Modelos',
     noTemplates: 'Ainda não existem modelos. Guarde uma tarefa como modelo para começar.',
     templateNameHint: 'Nome do modelo…',
@@ -1,6 +1,6 @@
 import { writable, get } from 'svelte/store';
 import { invoke } from '@tauri-apps/api/core';
-import type { Task, Project, Tag } from '$lib/types';
+import type { Task, Project, Tag, BatchOperation, BatchResult } from '$lib/types';
 
 export const tasks = writable<Task[]>([]);
 export const projects = writable<Project[]>([]);
@@ -9,6 +9,8 @@ export const searchQuery = writable<string>('');
 export const filterProject = writable<number | null>(null);
 export const filterTag = writable<number | null>(null);
 export const filterStatus = writable<string | null>(null);
+/** Ids of the tasks selected in the task list */
+export const selectedTaskIds = writable<number[]>([]);
 
 export async function refreshTasks() {
     try {
@@ -18,6 +20,8 @@ export async function refreshTasks() {
             filterStatus: get(filterStatus)
         });
         tasks.set(t);
+        // Drop selected ids that are no longer listed
+        selectedTaskIds.update(ids => ids.filter(id => t.some(task => task.id === id)));
     } catch (e) {
         console.error("Refresh tasks error:", e);
     }
@@ -53,3 +57,25 @@ export async function searchTasks(query: string) {
         console.error("Search tasks error:", e);
     }
 }
+
+/** Add `id` to the selection, or remove it if it is already selected */
+export function toggleTaskSelection(id: number) {
+    selectedTaskIds.update(ids => ids.includes(id) ? ids.filter(x => x !== id) : [...ids, id]);
+}
+
+/** Apply one operation to every selected task in a single transaction.
+ *  Tasks that succeeded are deselected; failures stay selected. */
+export async function applyToSelection(operation: BatchOperation): Promise<BatchResult[]> {
+    const ids = get(selectedTaskIds);
+    if (ids.length === 0) return [];
+    try {
+        const results = await invoke<BatchResult[]>('batch_update_tasks', { ids, operation });
+        const failed = new Set(results.filter(r => !r.ok).map(r => r.id));
+        selectedTaskIds.set(ids.filter(id => failed.has(id)));
+        await refreshTasks();
+        return results;
+    } catch (e) {
+        console.error("Batch update error:", e);
+        return [];
+    }
+}
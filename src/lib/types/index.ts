@@ -4,6 +4,34 @@ export interface Project {
     id: number;
     name: string;
     color?: string | null;
+    description?: string | null;
+    /** Optional parent project, for grouping */
+    parent_id?: number | null;
+    client?: string | null;
+    hourly_rate?: number | null;
+    /** ISO 4217 code, e.g. "EUR" */
+    currency?: string | null;
+    /** Hidden from `get_projects` unless `includeArchived` is set */
+    archived?: boolean;
+    /** Time budget in minutes, covering sub-projects too */
+    budget_minutes?: number | null;
+    budget_period?: BudgetPeriod;
+}
+
+export type BudgetPeriod = 'total' | 'monthly';
+
+/** Returned by `get_budget_status` */
+export interface BudgetStatus {
+    project_id: number;
+    project_name: string;
+    budget_minutes: number;
+    period: BudgetPeriod;
+    /** Start of the current month for a monthly budget */
+    period_start: number | null;
+    consumed_seconds: number;
+    /** Negative once the budget is overrun */
+    remaining_seconds: number;
+    percent_used: number;
 }
 
 export interface Tag {
@@ -12,7 +40,24 @@ export interface Tag {
     color?: string | null;
 }
 
-export type TaskStatus = 'todo' | 'doing' | 'done';
+/** Statuses of the default workflow; projects with their own workflow use its keys */
+export type TaskStatus = 'todo' | 'doing' | 'done' | (string & {});
+
+/** Returned by `get_workflow`; saved with `set_workflow` */
+export interface Workflow {
+    statuses: WorkflowStatus[];
+    /** Empty allows any move */
+    transitions?: { from: string; to: string }[];
+    /** External status (e.g. GitLab `opened`, Jira `In Progress`) → status key */
+    import_map?: Record<string, string>;
+}
+
+export interface WorkflowStatus {
+    key: string;
+    name: string;
+    done?: boolean;
+    wip_limit?: number | null;
+}
 
 export interface Task {
     id: number;
@@ -29,6 +74,102 @@ export interface Task {
     created_at: number;
     completed_at?: number | null;
     tags: Tag[];
+    /** Planned effort in minutes */
+    estimate_minutes?: number | null;
+    /** Completing the task creates its next occurrence */
+    recurrence?: Recurrence | null;
+    /** Computed: waiting on a task that isn't done */
+    blocked?: boolean;
+}
+
+/** Argument of `batch_update_tasks` */
+export type BatchOperation =
+    | { op: 'set_status'; status: TaskStatus }
+    | { op: 'set_project'; project_id: number | null }
+    | { op: 'set_priority'; priority: number }
+    | { op: 'set_due_date'; due_date: number | null }
+    | { op: 'add_tags'; tag_ids: number[] }
+    | { op: 'remove_tags'; tag_ids: number[] }
+    | { op: 'delete' };
+
+/** One entry per task id, returned by `batch_update_tasks` */
+export interface BatchResult {
+    id: number;
+    ok: boolean;
+    error: string | null;
+}
+
+/** `{placeholders}` in titles and descriptions are filled in by
+ *  `instantiate_template`; `{date}` and `{week}` are built in. */
+export interface TaskTemplate {
+    id: number;
+    name: string;
+    title: string;
+    description?: string | null;
+    project_id?: number | null;
+    priority?: number;
+    estimate_minutes?: number | null;
+    tags?: Tag[];
+    subtasks?: TemplateSubtask[];
+    created_at?: number;
+}
+
+export interface TemplateSubtask {
+    title: string;
+    description?: string | null;
+    estimate_minutes?: number | null;
+    subtasks?: TemplateSubtask[];
+}
+
+/** Payload of the `due-reminders` event */
+export interface Reminder {
+    task_id: number;
+    title: string;
+    due_date: number;
+    kind: { kind: 'upcoming'; minutes_before: number } | { kind: 'overdue' };
+}
+
+/** Weekdays are 0 = Monday … 6 = Sunday */
+export type Recurrence =
+    | { kind: 'daily' }
+    | { kind: 'weekdays' }
+    | { kind: 'weekly'; days: number[] }
+    | { kind: 'monthly'; day: number }
+    | { kind: 'after_completion'; days: number };
+
+/** `task_id` is blocked by `blocked_by` */
+export interface TaskDependency {
+    task_id: number;
+    blocked_by: number;
+}
+
+/** Returned by `get_dependency_graph` */
+export interface DependencyGraph {
+    tasks: Task[];
+    edges: TaskDependency[];
+}
+
+/** Totals for a task and everything beneath it */
+export interface TaskRollup {
+    tracked_seconds: number;
+    estimate_minutes: number;
+    subtasks_total: number;
+    subtasks_done: number;
+    /** 0–100, or null for a task without subtasks */
+    percent_done: number | null;
+}
+
+/** Returned by `get_task_tree` */
+export interface TaskNode {
+    task: Task;
+    children: TaskNode[];
+    rollup: TaskRollup;
+}
+
+/** Returned by `complete_task` */
+export interface TaskCompletion {
+    completed: boolean;
+    open_subtasks: Task[];
 }
 
 // ── Timer ──────────────────────────────────────────────────────────────────
@@ -62,6 +203,167 @@ export interface DailyStat {
     total_seconds: number;
 }
 
+export type StatsGroup = 'project' | 'tag' | 'source' | 'status';
+
+/** Returned by `get_grouped_stats`; work that can't be attributed is in the
+ *  "Unassigned" bucket, which has `unassigned` set */
+export interface GroupStat {
+    /** Project or tag id */
+    id: number | null;
+    name: string;
+    unassigned: boolean;
+    sessions: number;
+    total_seconds: number;
+}
+
+export type TrendPeriod = 'week' | 'month';
+export type SeriesBy = 'task' | 'project';
+
+/** Returned by `get_trends` */
+export interface PeriodTrend {
+    /** First day of the week "YYYY-MM-DD", or the month "YYYY-MM" */
+    period: string;
+    sessions: number;
+    total_seconds: number;
+    /** Days with at least one completed work session */
+    working_days: number;
+    avg_seconds_per_working_day: number;
+    previous_seconds: number;
+    last_year_seconds: number;
+    delta_previous: number;
+    delta_last_year: number;
+}
+
+/** Returned by `get_focus_heatmap`; rows are weekdays, columns local hours */
+export interface FocusHeatmap {
+    /** Weekday of each row, 0 = Monday … 6 = Sunday */
+    weekdays: number[];
+    /** 7 × 24 */
+    minutes: number[][];
+    /** Sessions by the cell they started in, 7 × 24 */
+    sessions: number[][];
+}
+
+export type SessionOutcome = 'completed' | 'reset' | 'switched' | 'unfinished';
+
+export interface FocusSession {
+    task_name: string;
+    started_at: number;
+    ended_at: number;
+    outcome: SessionOutcome;
+    focus_seconds: number;
+    pauses: number;
+    pause_seconds: number;
+    avg_stretch_seconds: number;
+    /** 0–100 */
+    focus_score: number;
+}
+
+export interface FocusDay {
+    /** ISO date string "YYYY-MM-DD" */
+    day: string;
+    sessions: number;
+    completed: number;
+    reset: number;
+    switched: number;
+    focus_seconds: number;
+    pauses: number;
+    pause_seconds: number;
+    avg_stretch_seconds: number;
+    focus_score: number;
+}
+
+/** Returned by `get_focus_metrics` */
+export interface FocusReport {
+    sessions: FocusSession[];
+    days: FocusDay[];
+}
+
+export interface SwitchDay {
+    /** ISO date string "YYYY-MM-DD" */
+    day: string;
+    switches: number;
+    /** Switches that cut a running work session short */
+    mid_session: number;
+}
+
+export interface SwitchPair {
+    from_task: string;
+    to_task: string;
+    count: number;
+}
+
+/** Returned by `get_switch_stats` */
+export interface SwitchReport {
+    switches: number;
+    mid_session: number;
+    avg_seconds_before_switch: number;
+    days: SwitchDay[];
+    /** Most frequent first */
+    top_pairs: SwitchPair[];
+}
+
+/** Returned by `get_trend_series`; each line has one value per period */
+export interface TrendSeries {
+    periods: string[];
+    lines: SeriesLine[];
+}
+
+export interface SeriesLine {
+    id: number | null;
+    /** "Unassigned" for the unassigned line */
+    name: string;
+    unassigned: boolean;
+    seconds: number[];
+}
+
+// ── Billing ────────────────────────────────────────────────────────────────
+
+export type Rounding = 'none' | 'per_session' | 'per_day';
+export type InvoiceFormat = 'markdown' | 'html' | 'csv';
+
+/** Argument of `get_billing_report` / `render_invoice_draft` */
+export interface BillingQuery {
+    /** Inclusive window, unix seconds */
+    start: number;
+    end: number;
+    project_id?: number | null;
+    client?: string | null;
+    rounding?: Rounding;
+    /** Rounding increment, e.g. 6, 15 or 30 (default 15) */
+    round_to_minutes?: number;
+}
+
+export interface InvoiceLine {
+    project_id: number | null;
+    project_name: string;
+    task_name: string;
+    sessions: number;
+    tracked_seconds: number;
+    billable_minutes: number;
+    hourly_rate: number | null;
+    currency: string | null;
+    /** null when the project has no hourly rate */
+    amount: number | null;
+}
+
+export interface CurrencyTotal {
+    currency: string | null;
+    billable_minutes: number;
+    amount: number;
+}
+
+/** Returned by `get_billing_report` */
+export interface BillingReport {
+    start: number;
+    end: number;
+    client: string | null;
+    lines: InvoiceLine[];
+    tracked_seconds: number;
+    billable_minutes: number;
+    totals: CurrencyTotal[];
+}
+
 // ── Integrations ───────────────────────────────────────────────────────────
 
 export interface ExternalTask {